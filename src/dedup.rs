@@ -0,0 +1,243 @@
+use crate::r#struct::betterstack_log_schema::BetterStackLogSchema;
+use crate::r#struct::log_level::LogLevel;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+type Key = (LogLevel, String, String);
+
+struct Window {
+    opened_at: Instant,
+    /// Most recent repeat, shipped with `repeat_count` when the window closes.
+    last: Option<BetterStackLogSchema>,
+    repeats: u64,
+}
+
+/// Collapses records with the same level, message and context seen within `window`.
+///
+/// The first record of a window goes through untouched, the repeats that follow are held
+/// back and released as a single copy of the latest one carrying `repeat_count`.
+pub(crate) struct Dedup {
+    window: Duration,
+    state: Mutex<State>,
+}
+
+#[derive(Default)]
+struct State {
+    windows: HashMap<Key, Window>,
+    /// A timer is waiting to release the repeats of expiring windows.
+    timer_armed: bool,
+}
+
+impl Dedup {
+    pub(crate) fn new(window: Duration) -> Self {
+        Self {
+            window,
+            state: Mutex::new(State::default()),
+        }
+    }
+
+    /// Records to dispatch now: the collapsed repeats of expired windows, then `log`
+    /// unless it repeats an open window.
+    pub(crate) fn observe(&self, log: &BetterStackLogSchema) -> Vec<BetterStackLogSchema> {
+        let mut state = self.state.lock().unwrap();
+        let windows = &mut state.windows;
+        let mut ready = drain(windows, |window| window.opened_at.elapsed() >= self.window);
+
+        let key = (log.level.clone(), log.message.clone(), log.context.clone());
+        match windows.get_mut(&key) {
+            Some(window) => {
+                window.repeats += 1;
+                window.last = Some(log.clone());
+            }
+            None => {
+                windows.insert(
+                    key,
+                    Window {
+                        opened_at: Instant::now(),
+                        last: None,
+                        repeats: 0,
+                    },
+                );
+                ready.push(log.clone());
+            }
+        }
+        ready
+    }
+
+    /// The collapsed repeats of expired windows.
+    pub(crate) fn expired(&self) -> Vec<BetterStackLogSchema> {
+        let windows = &mut self.state.lock().unwrap().windows;
+        drain(windows, |window| window.opened_at.elapsed() >= self.window)
+    }
+
+    /// The collapsed repeats of every window, open or not.
+    pub(crate) fn flush(&self) -> Vec<BetterStackLogSchema> {
+        drain(&mut self.state.lock().unwrap().windows, |_| true)
+    }
+
+    /// Whether the caller should start a timer: repeats are held back and no timer is
+    /// armed yet.
+    pub(crate) fn arm_timer(&self) -> bool {
+        let mut state = self.state.lock().unwrap();
+        if state.timer_armed || !holds_repeats(&state.windows) {
+            return false;
+        }
+        state.timer_armed = true;
+        true
+    }
+
+    /// When the next window holding repeats expires, disarming the timer once none does.
+    pub(crate) fn next_expiry(&self) -> Option<Instant> {
+        let mut state = self.state.lock().unwrap();
+        let next = state
+            .windows
+            .values()
+            .filter(|window| window.last.is_some())
+            .map(|window| window.opened_at + self.window)
+            .min();
+        state.timer_armed = next.is_some();
+        next
+    }
+
+    pub(crate) fn disarm_timer(&self) {
+        self.state.lock().unwrap().timer_armed = false;
+    }
+}
+
+fn holds_repeats(windows: &HashMap<Key, Window>) -> bool {
+    windows.values().any(|window| window.last.is_some())
+}
+
+fn drain(
+    windows: &mut HashMap<Key, Window>,
+    mut closes: impl FnMut(&Window) -> bool,
+) -> Vec<BetterStackLogSchema> {
+    let mut closed = Vec::new();
+    windows.retain(|_, window| {
+        if !closes(window) {
+            return true;
+        }
+        if let Some(mut last) = window.last.take() {
+            last.repeat_count = Some(window.repeats);
+            closed.push(last);
+        }
+        false
+    });
+    closed.sort_by_key(|log| log.dt);
+    closed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log(level: LogLevel, message: &str) -> BetterStackLogSchema {
+        BetterStackLogSchema::fixture(level, message)
+    }
+
+    #[test]
+    fn first_record_passes_through() {
+        let dedup = Dedup::new(Duration::from_secs(60));
+
+        let ready = dedup.observe(&log(LogLevel::Error, "boom"));
+
+        assert_eq!(ready.len(), 1);
+        assert_eq!(ready[0].repeat_count, None);
+    }
+
+    #[test]
+    fn repeats_within_window_are_held_back() {
+        let dedup = Dedup::new(Duration::from_secs(60));
+        dedup.observe(&log(LogLevel::Error, "boom"));
+
+        assert!(dedup.observe(&log(LogLevel::Error, "boom")).is_empty());
+        assert!(dedup.observe(&log(LogLevel::Error, "boom")).is_empty());
+    }
+
+    #[test]
+    fn level_message_and_context_make_the_key() {
+        let dedup = Dedup::new(Duration::from_secs(60));
+        dedup.observe(&log(LogLevel::Error, "boom"));
+
+        let other_context = BetterStackLogSchema {
+            context: "elsewhere".to_string(),
+            ..log(LogLevel::Error, "boom")
+        };
+        assert_eq!(dedup.observe(&log(LogLevel::Warn, "boom")).len(), 1);
+        assert_eq!(dedup.observe(&log(LogLevel::Error, "bang")).len(), 1);
+        assert_eq!(dedup.observe(&other_context).len(), 1);
+    }
+
+    #[test]
+    fn expired_window_releases_repeat_count() {
+        let dedup = Dedup::new(Duration::from_millis(100));
+        dedup.observe(&log(LogLevel::Error, "boom"));
+        dedup.observe(&log(LogLevel::Error, "boom"));
+        dedup.observe(&log(LogLevel::Error, "boom"));
+        std::thread::sleep(Duration::from_millis(150));
+
+        let ready = dedup.observe(&log(LogLevel::Error, "boom"));
+
+        assert_eq!(ready.len(), 2);
+        assert_eq!(ready[0].repeat_count, Some(2));
+        assert_eq!(ready[1].repeat_count, None);
+    }
+
+    #[test]
+    fn expired_window_without_repeats_releases_nothing() {
+        let dedup = Dedup::new(Duration::from_millis(100));
+        dedup.observe(&log(LogLevel::Error, "boom"));
+        std::thread::sleep(Duration::from_millis(150));
+
+        let ready = dedup.observe(&log(LogLevel::Info, "other"));
+
+        assert_eq!(ready.len(), 1);
+        assert_eq!(ready[0].message, "other");
+    }
+
+    #[test]
+    fn expired_releases_only_expired_windows() {
+        let dedup = Dedup::new(Duration::from_millis(100));
+        dedup.observe(&log(LogLevel::Error, "boom"));
+        dedup.observe(&log(LogLevel::Error, "boom"));
+
+        assert!(dedup.expired().is_empty());
+        std::thread::sleep(Duration::from_millis(150));
+        let expired = dedup.expired();
+
+        assert_eq!(expired.len(), 1);
+        assert_eq!(expired[0].repeat_count, Some(1));
+    }
+
+    #[test]
+    fn timer_is_armed_once_while_repeats_are_held() {
+        let dedup = Dedup::new(Duration::from_secs(60));
+        dedup.observe(&log(LogLevel::Error, "boom"));
+        assert!(!dedup.arm_timer());
+
+        dedup.observe(&log(LogLevel::Error, "boom"));
+
+        assert!(dedup.arm_timer());
+        assert!(!dedup.arm_timer());
+        assert!(dedup.next_expiry().is_some());
+        dedup.flush();
+        assert!(dedup.next_expiry().is_none());
+        dedup.observe(&log(LogLevel::Error, "bang"));
+        dedup.observe(&log(LogLevel::Error, "bang"));
+        assert!(dedup.arm_timer());
+    }
+
+    #[test]
+    fn flush_releases_open_windows() {
+        let dedup = Dedup::new(Duration::from_secs(60));
+        dedup.observe(&log(LogLevel::Error, "boom"));
+        dedup.observe(&log(LogLevel::Error, "boom"));
+
+        let flushed = dedup.flush();
+
+        assert_eq!(flushed.len(), 1);
+        assert_eq!(flushed[0].repeat_count, Some(1));
+        assert!(dedup.flush().is_empty());
+    }
+}
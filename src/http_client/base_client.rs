@@ -1,48 +1,165 @@
-use super::{HttpClient, LogtailError};
-use reqwest::header::{HeaderMap, HeaderValue};
-use serde_json::Value;
+#[cfg(feature = "compression")]
+use super::compression::CompressionConfig;
+use super::{Encoding, HttpClient, HttpRequest, HttpResponse, LogtailError};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, CONTENT_ENCODING, CONTENT_TYPE};
+use std::time::Duration;
 
-pub struct ReqwestClient;
+/// Per-request limits of [`ReqwestClient`], see [`DeliveryConfig`](crate::DeliveryConfig)
+/// for the deadline covering retries.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Timeouts {
+    /// Establishing the TCP and TLS connection.
+    pub connect: Duration,
+    /// A single request, from sending it to reading the whole response.
+    pub request: Duration,
+}
+
+impl Default for Timeouts {
+    fn default() -> Self {
+        Self {
+            connect: Duration::from_secs(5),
+            request: Duration::from_secs(10),
+        }
+    }
+}
+
+pub struct ReqwestClient {
+    client: reqwest::Client,
+    timeouts: Timeouts,
+    encoding: Encoding,
+    #[cfg(feature = "compression")]
+    compression: Option<CompressionConfig>,
+}
+
+impl Default for ReqwestClient {
+    fn default() -> Self {
+        Self {
+            client: build_client(&Timeouts::default(), true),
+            timeouts: Timeouts::default(),
+            encoding: Encoding::default(),
+            #[cfg(feature = "compression")]
+            compression: None,
+        }
+    }
+}
+
+impl ReqwestClient {
+    /// Same as [`ReqwestClient::default`], what `ReqwestClient` as a unit value was before 0.3.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_timeouts(mut self, timeouts: Timeouts) -> Self {
+        self.client = build_client(&timeouts, true);
+        self.timeouts = timeouts;
+        self
+    }
+
+    /// Serializes logs with `encoding` instead of JSON.
+    pub fn with_encoding(mut self, encoding: Encoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
+    /// Compresses request bodies, setting `Content-Encoding` accordingly.
+    #[cfg(feature = "compression")]
+    pub fn with_compression(mut self, compression: CompressionConfig) -> Self {
+        self.compression = Some(compression);
+        self
+    }
+
+    /// Compresses the body when configured, returning it along with its `Content-Encoding`.
+    fn encode_body(&self, bytes: Vec<u8>) -> Result<(Vec<u8>, Option<&'static str>), LogtailError> {
+        #[cfg(feature = "compression")]
+        if let Some(compression) = &self.compression {
+            return Ok(compression.encode(bytes)?);
+        }
+
+        Ok((bytes, None))
+    }
+}
 
 impl HttpClient for ReqwestClient {
-    async fn post_json(
-        &self,
-        url: &str,
-        body: &Value,
-        extra_headers: Option<HeaderMap>,
-    ) -> Result<Option<Value>, LogtailError> {
+    async fn send(&self, request: HttpRequest) -> Result<HttpResponse, LogtailError> {
         let mut header_map = HeaderMap::new();
-
-        if let Some(value) = extra_headers {
-            header_map.extend(value.iter().map(|(k, v)| (k.clone(), v.clone())))
+        for (name, value) in &request.headers {
+            header_map.append(header_name(name)?, header_value(value)?);
         }
+        header_map.insert(CONTENT_TYPE, header_value(&request.content_type)?);
+        let mut headers = build_headers(Some(header_map));
 
-        let client = reqwest::Client::new();
+        // bodies the caller already compressed are sent as they are
+        let (body, content_encoding) = match request.content_encoding {
+            Some(encoding) => (request.body, Some(encoding)),
+            None => {
+                let (body, encoding) = self.encode_body(request.body)?;
+                (body, encoding.map(str::to_string))
+            }
+        };
+        if let Some(encoding) = content_encoding {
+            headers.insert(CONTENT_ENCODING, header_value(&encoding)?);
+        }
 
-        let response = client
-            .post(url)
-            .headers(build_headers(Some(header_map)))
-            .json(body)
+        let response = self
+            .client
+            .post(&request.url)
+            .headers(headers)
+            .body(body)
             .send()
             .await?;
 
-        if response.status().is_success() {
-            let body_bytes = response.bytes().await?;
+        let status = response.status().as_u16();
+        let headers = response
+            .headers()
+            .iter()
+            .map(|(name, value)| {
+                let value = String::from_utf8_lossy(value.as_bytes()).into_owned();
+                (name.as_str().to_string(), value)
+            })
+            .collect();
+        let body = response.bytes().await?.to_vec();
 
-            if !body_bytes.is_empty() {
-                let result_value: Value = serde_json::from_slice(&body_bytes)?;
-                return Ok(Some(result_value));
-            }
+        Ok(HttpResponse {
+            status,
+            headers,
+            body,
+        })
+    }
 
-            Ok(None)
-        } else {
-            let status = response.status().as_u16();
-            Err(LogtailError::Http {
-                status,
-                message: format!("HTTP request failed with status {}", status),
-            })
-        }
+    fn encoding(&self) -> Encoding {
+        self.encoding
     }
+
+    fn unpooled(&self) -> Option<Self> {
+        Some(Self {
+            client: build_client(&self.timeouts, false),
+            timeouts: self.timeouts.clone(),
+            encoding: self.encoding,
+            #[cfg(feature = "compression")]
+            compression: self.compression.clone(),
+        })
+    }
+}
+
+/// One client per `ReqwestClient`, so connections are pooled across requests unless
+/// `pooled` is off.
+fn build_client(timeouts: &Timeouts, pooled: bool) -> reqwest::Client {
+    let mut builder = reqwest::Client::builder()
+        .connect_timeout(timeouts.connect)
+        .timeout(timeouts.request);
+    if !pooled {
+        builder = builder.pool_max_idle_per_host(0);
+    }
+    builder.build().expect("failed to build HTTP client")
+}
+
+fn header_name(name: &str) -> Result<HeaderName, LogtailError> {
+    HeaderName::from_bytes(name.as_bytes())
+        .map_err(|_| LogtailError::InvalidHeader(name.to_string()))
+}
+
+fn header_value(value: &str) -> Result<HeaderValue, LogtailError> {
+    HeaderValue::from_str(value).map_err(|_| LogtailError::InvalidHeader(value.to_string()))
 }
 
 fn build_headers(input_headers: Option<HeaderMap>) -> HeaderMap {
@@ -87,6 +204,168 @@ mod tests {
         assert_eq!(headers.len(), 2);
     }
 
+    fn json_request(url: &str, body: &[u8]) -> HttpRequest {
+        HttpRequest {
+            url: url.to_string(),
+            body: body.to_vec(),
+            content_type: "application/json".to_string(),
+            content_encoding: None,
+            headers: vec![("Authorization".to_string(), "Bearer token".to_string())],
+        }
+    }
+
+    #[tokio::test]
+    async fn send_posts_body_and_headers() {
+        let (url, received) = capture_one_request(OK_EMPTY);
+
+        let response = ReqwestClient::default()
+            .send(json_request(&url, br#"{"message":"hello"}"#))
+            .await
+            .unwrap();
+
+        assert_eq!(response.status, 200);
+        assert!(response.body.is_empty());
+        let request = received.recv().unwrap();
+        assert_eq!(request.header("content-type"), Some("application/json"));
+        assert_eq!(request.header("authorization"), Some("Bearer token"));
+        assert_eq!(request.header("accept"), Some("application/json"));
+        assert_eq!(request.header("content-encoding"), None);
+        assert_eq!(request.body, br#"{"message":"hello"}"#);
+    }
+
+    #[tokio::test]
+    async fn send_returns_error_statuses_as_responses() {
+        let (url, _received) = capture_one_request(
+            "HTTP/1.1 429 Too Many Requests\r\nRetry-After: 5\r\nContent-Length: 4\r\nConnection: close\r\n\r\nslow",
+        );
+
+        let response = ReqwestClient::default()
+            .send(json_request(&url, b"{}"))
+            .await
+            .unwrap();
+
+        assert_eq!(response.status, 429);
+        assert_eq!(response.header("retry-after"), Some("5"));
+        assert_eq!(response.body, b"slow");
+    }
+
+    #[tokio::test]
+    async fn send_keeps_precompressed_body() {
+        let (url, received) = capture_one_request(OK_EMPTY);
+        let mut request = json_request(&url, &[0x1f, 0x8b]);
+        request.content_encoding = Some("gzip".to_string());
+
+        ReqwestClient::default().send(request).await.unwrap();
+
+        let request = received.recv().unwrap();
+        assert_eq!(request.header("content-encoding"), Some("gzip"));
+        assert_eq!(request.body, vec![0x1f, 0x8b]);
+    }
+
+    #[tokio::test]
+    async fn send_rejects_invalid_header() {
+        let mut request = json_request("http://127.0.0.1:9", b"{}");
+        request
+            .headers
+            .push(("bad header".to_string(), "x".to_string()));
+
+        let result = ReqwestClient::default().send(request).await;
+
+        assert!(matches!(result, Err(LogtailError::InvalidHeader(_))));
+    }
+
+    #[tokio::test]
+    async fn send_times_out_slow_responses() {
+        use crate::test_util::{FakeIngestServer, ScriptedResponse};
+
+        let server = FakeIngestServer::start("token").unwrap();
+        server.then_respond(ScriptedResponse::Success.delayed(Duration::from_millis(500)));
+        let client = ReqwestClient::default().with_timeouts(Timeouts {
+            connect: Duration::from_secs(1),
+            request: Duration::from_millis(50),
+        });
+
+        let result = client.send(json_request(&server.url(), b"{}")).await;
+
+        assert!(matches!(result, Err(LogtailError::Timeout)));
+    }
+
+    #[tokio::test]
+    async fn unpooled_keeps_settings() {
+        use crate::test_util::FakeIngestServer;
+
+        let server = FakeIngestServer::start("secret").unwrap();
+        let client = ReqwestClient::default()
+            .with_encoding(Encoding::MessagePack)
+            .unpooled()
+            .unwrap();
+
+        let response = client
+            .send(json_request(&server.url(), b"{}"))
+            .await
+            .unwrap();
+
+        assert_eq!(client.encoding(), Encoding::MessagePack);
+        assert_eq!(response.status, 401);
+    }
+
+    #[test]
+    fn encoding_defaults_to_json() {
+        assert_eq!(ReqwestClient::default().encoding(), Encoding::Json);
+        assert_eq!(
+            ReqwestClient::default()
+                .with_encoding(Encoding::MessagePack)
+                .encoding(),
+            Encoding::MessagePack
+        );
+    }
+
+    #[cfg(feature = "compression")]
+    #[tokio::test]
+    async fn send_gzips_body_above_threshold() {
+        use crate::http_client::{Compression, CompressionConfig};
+        use flate2::read::GzDecoder;
+        use std::io::Read;
+
+        let (url, received) = capture_one_request(OK_EMPTY);
+        let client = ReqwestClient::default().with_compression(CompressionConfig {
+            algorithm: Compression::Gzip,
+            min_bytes: 16,
+        });
+        let body = serde_json::to_vec(&serde_json::json!({"message": "x".repeat(100)})).unwrap();
+
+        client.send(json_request(&url, &body)).await.unwrap();
+
+        let request = received.recv().unwrap();
+        assert_eq!(request.header("content-encoding"), Some("gzip"));
+        let mut decoded = Vec::new();
+        GzDecoder::new(request.body.as_slice())
+            .read_to_end(&mut decoded)
+            .unwrap();
+        assert_eq!(decoded, body);
+    }
+
+    #[cfg(feature = "compression")]
+    #[tokio::test]
+    async fn send_skips_compression_below_threshold() {
+        use crate::http_client::{Compression, CompressionConfig};
+
+        let (url, received) = capture_one_request(OK_EMPTY);
+        let client = ReqwestClient::default().with_compression(CompressionConfig {
+            algorithm: Compression::Gzip,
+            min_bytes: 1024,
+        });
+
+        client
+            .send(json_request(&url, br#"{"message":"hi"}"#))
+            .await
+            .unwrap();
+
+        let request = received.recv().unwrap();
+        assert_eq!(request.header("content-encoding"), None);
+        assert_eq!(request.body, br#"{"message":"hi"}"#);
+    }
+
     #[test]
     fn build_headers_accept_overwrites_extra() {
         let mut extra = HeaderMap::new();
@@ -98,4 +377,61 @@ mod tests {
             HeaderValue::from_static("application/json")
         );
     }
+
+    struct CapturedRequest {
+        headers: Vec<(String, String)>,
+        body: Vec<u8>,
+    }
+
+    impl CapturedRequest {
+        fn header(&self, name: &str) -> Option<&str> {
+            self.headers
+                .iter()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.as_str())
+        }
+    }
+
+    const OK_EMPTY: &str = "HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+
+    /// Accepts a single HTTP request on a local port, answering with the raw `response`.
+    fn capture_one_request(
+        response: &'static str,
+    ) -> (String, std::sync::mpsc::Receiver<CapturedRequest>) {
+        use std::io::{BufRead, BufReader, Read, Write};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let (sender, receiver) = std::sync::mpsc::channel();
+
+        std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+            let mut headers = Vec::new();
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                let line = line.trim_end();
+                if line.is_empty() {
+                    break;
+                }
+                if let Some((key, value)) = line.split_once(':') {
+                    headers.push((key.to_lowercase(), value.trim().to_string()));
+                }
+            }
+            let length = headers
+                .iter()
+                .find(|(key, _)| key == "content-length")
+                .map(|(_, value)| value.parse().unwrap())
+                .unwrap_or(0);
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body).unwrap();
+
+            let mut stream = reader.into_inner();
+            stream.write_all(response.as_bytes()).unwrap();
+            sender.send(CapturedRequest { headers, body }).unwrap();
+        });
+
+        (url, receiver)
+    }
 }
@@ -0,0 +1,297 @@
+use super::{ErrorHook, LogtailError};
+use std::fmt;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// When to stop calling a failing endpoint, and for how long.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CircuitBreakerConfig {
+    /// Consecutive failed deliveries that open the circuit.
+    pub failure_threshold: u32,
+    /// How long the circuit stays open before a single probe is let through, and how long
+    /// that probe has to report back before the circuit opens again.
+    pub open_for: Duration,
+    /// Records kept while the circuit is open and sent once it closes, `0` drops them.
+    pub spool_capacity: usize,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self {
+            failure_threshold: 5,
+            open_for: Duration::from_secs(30),
+            spool_capacity: 100,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitState {
+    /// Requests go through.
+    Closed,
+    /// Requests are short-circuited.
+    Open { failures: u32 },
+    /// A probe request is deciding whether to close the circuit again.
+    HalfOpen,
+}
+
+impl fmt::Display for CircuitState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CircuitState::Closed => write!(f, "closed"),
+            CircuitState::Open { failures } => {
+                write!(f, "open after {} consecutive failures", failures)
+            }
+            CircuitState::HalfOpen => write!(f, "half-open"),
+        }
+    }
+}
+
+struct Inner {
+    state: CircuitState,
+    failures: u32,
+    /// When the circuit last opened, or when the half-open probe was let through.
+    opened_at: Instant,
+}
+
+/// Tracks consecutive delivery failures and decides whether the next request is sent.
+///
+/// Transitions are reported to the hook as [`LogtailError::Circuit`].
+pub struct CircuitBreaker {
+    config: CircuitBreakerConfig,
+    inner: Mutex<Inner>,
+    hook: Option<ErrorHook>,
+}
+
+impl CircuitBreaker {
+    pub fn new(config: CircuitBreakerConfig) -> Self {
+        Self {
+            config,
+            inner: Mutex::new(Inner {
+                state: CircuitState::Closed,
+                failures: 0,
+                opened_at: Instant::now(),
+            }),
+            hook: None,
+        }
+    }
+
+    pub fn with_hook(mut self, hook: ErrorHook) -> Self {
+        self.hook = Some(hook);
+        self
+    }
+
+    pub fn config(&self) -> &CircuitBreakerConfig {
+        &self.config
+    }
+
+    pub fn state(&self) -> CircuitState {
+        self.inner.lock().unwrap().state
+    }
+
+    /// Whether a request may be sent now. Once `open_for` has elapsed, the first caller
+    /// becomes the half-open probe and everyone else is still turned away. A probe that
+    /// reports nothing within `open_for`, e.g. because it was cancelled, opens the circuit again.
+    pub fn allow(&self) -> bool {
+        let mut inner = self.inner.lock().unwrap();
+        match inner.state {
+            CircuitState::Closed => true,
+            CircuitState::HalfOpen => {
+                if inner.opened_at.elapsed() < self.config.open_for {
+                    return false;
+                }
+                let state = CircuitState::Open {
+                    failures: inner.failures,
+                };
+                inner.state = state;
+                inner.opened_at = Instant::now();
+                drop(inner);
+                self.report(state);
+                false
+            }
+            CircuitState::Open { .. } => {
+                if inner.opened_at.elapsed() < self.config.open_for {
+                    return false;
+                }
+                inner.state = CircuitState::HalfOpen;
+                inner.opened_at = Instant::now();
+                drop(inner);
+                self.report(CircuitState::HalfOpen);
+                true
+            }
+        }
+    }
+
+    /// The endpoint answered, closing the circuit if it was not already.
+    pub fn record_success(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.failures = 0;
+        if inner.state == CircuitState::Closed {
+            return;
+        }
+        inner.state = CircuitState::Closed;
+        drop(inner);
+        self.report(CircuitState::Closed);
+    }
+
+    /// The endpoint could not be reached, opening the circuit at the threshold or on a
+    /// failed probe.
+    pub fn record_failure(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.failures = inner.failures.saturating_add(1);
+        let opens = match inner.state {
+            CircuitState::Closed => inner.failures >= self.config.failure_threshold,
+            CircuitState::HalfOpen => true,
+            CircuitState::Open { .. } => false,
+        };
+        if !opens {
+            return;
+        }
+        let state = CircuitState::Open {
+            failures: inner.failures,
+        };
+        inner.state = state;
+        inner.opened_at = Instant::now();
+        drop(inner);
+        self.report(state);
+    }
+
+    /// Continues where `previous` left off, keeping this breaker's config and hook.
+    pub(crate) fn restore(&self, previous: CircuitBreaker) {
+        *self.inner.lock().unwrap() = previous.inner.into_inner().unwrap();
+    }
+
+    fn report(&self, state: CircuitState) {
+        if let Some(hook) = &self.hook {
+            hook(&LogtailError::Circuit(state));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    fn breaker(failure_threshold: u32, open_for: Duration) -> CircuitBreaker {
+        CircuitBreaker::new(CircuitBreakerConfig {
+            failure_threshold,
+            open_for,
+            spool_capacity: 0,
+        })
+    }
+
+    fn recording_hook() -> (ErrorHook, Arc<Mutex<Vec<String>>>) {
+        let reported = Arc::new(Mutex::new(Vec::new()));
+        let sink = reported.clone();
+        let hook: ErrorHook = Arc::new(move |err| sink.lock().unwrap().push(err.to_string()));
+        (hook, reported)
+    }
+
+    #[test]
+    fn opens_after_consecutive_failures() {
+        let breaker = breaker(3, Duration::from_secs(60));
+
+        breaker.record_failure();
+        breaker.record_failure();
+        assert!(breaker.allow());
+        breaker.record_failure();
+
+        assert_eq!(breaker.state(), CircuitState::Open { failures: 3 });
+        assert!(!breaker.allow());
+    }
+
+    #[test]
+    fn success_resets_failure_count() {
+        let breaker = breaker(2, Duration::from_secs(60));
+
+        breaker.record_failure();
+        breaker.record_success();
+        breaker.record_failure();
+
+        assert_eq!(breaker.state(), CircuitState::Closed);
+    }
+
+    #[test]
+    fn lets_one_probe_through_after_open_for() {
+        let breaker = breaker(1, Duration::ZERO);
+        breaker.record_failure();
+
+        assert!(breaker.allow());
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+        assert!(!breaker.allow());
+    }
+
+    #[test]
+    fn successful_probe_closes() {
+        let breaker = breaker(1, Duration::ZERO);
+        breaker.record_failure();
+        breaker.allow();
+
+        breaker.record_success();
+
+        assert_eq!(breaker.state(), CircuitState::Closed);
+        assert!(breaker.allow());
+    }
+
+    #[test]
+    fn failed_probe_reopens() {
+        let breaker = breaker(1, Duration::from_millis(20));
+        breaker.record_failure();
+        std::thread::sleep(Duration::from_millis(30));
+        assert!(breaker.allow());
+
+        breaker.record_failure();
+
+        assert_eq!(breaker.state(), CircuitState::Open { failures: 2 });
+        assert!(!breaker.allow());
+    }
+
+    #[test]
+    fn abandoned_probe_reopens() {
+        let breaker = breaker(1, Duration::from_millis(20));
+        breaker.record_failure();
+        std::thread::sleep(Duration::from_millis(30));
+        assert!(breaker.allow());
+
+        std::thread::sleep(Duration::from_millis(30));
+
+        assert!(!breaker.allow());
+        assert_eq!(breaker.state(), CircuitState::Open { failures: 1 });
+        std::thread::sleep(Duration::from_millis(30));
+        assert!(breaker.allow());
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+    }
+
+    #[test]
+    fn restore_keeps_state_of_previous_breaker() {
+        let previous = breaker(1, Duration::from_secs(60));
+        previous.record_failure();
+
+        let breaker = breaker(1, Duration::from_secs(60));
+        breaker.restore(previous);
+
+        assert_eq!(breaker.state(), CircuitState::Open { failures: 1 });
+        assert!(!breaker.allow());
+    }
+
+    #[test]
+    fn reports_transitions_to_hook() {
+        let (hook, reported) = recording_hook();
+        let breaker = breaker(2, Duration::ZERO).with_hook(hook);
+
+        breaker.record_failure();
+        breaker.record_failure();
+        breaker.allow();
+        breaker.record_success();
+
+        assert_eq!(
+            *reported.lock().unwrap(),
+            vec![
+                "circuit breaker open after 2 consecutive failures",
+                "circuit breaker half-open",
+                "circuit breaker closed",
+            ]
+        );
+    }
+}
@@ -0,0 +1,107 @@
+use std::io::{self, Write};
+
+/// Algorithm used to compress request bodies.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Compression {
+    Gzip,
+    #[cfg(feature = "zstd")]
+    Zstd,
+}
+
+impl Compression {
+    /// Value of the `Content-Encoding` header for bodies compressed with this algorithm.
+    pub fn content_encoding(&self) -> &'static str {
+        match self {
+            Compression::Gzip => "gzip",
+            #[cfg(feature = "zstd")]
+            Compression::Zstd => "zstd",
+        }
+    }
+
+    pub(crate) fn compress(&self, body: &[u8]) -> io::Result<Vec<u8>> {
+        match self {
+            Compression::Gzip => {
+                let mut encoder =
+                    flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder.write_all(body)?;
+                encoder.finish()
+            }
+            #[cfg(feature = "zstd")]
+            Compression::Zstd => zstd::encode_all(body, 0),
+        }
+    }
+}
+
+/// Compresses request bodies of at least `min_bytes`, smaller ones are not worth the CPU.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompressionConfig {
+    pub algorithm: Compression,
+    pub min_bytes: usize,
+}
+
+impl CompressionConfig {
+    /// Returns the body to send and its `Content-Encoding`, `None` when left uncompressed.
+    pub(crate) fn encode(&self, body: Vec<u8>) -> io::Result<(Vec<u8>, Option<&'static str>)> {
+        if body.len() < self.min_bytes {
+            return Ok((body, None));
+        }
+        let compressed = self.algorithm.compress(&body)?;
+        Ok((compressed, Some(self.algorithm.content_encoding())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::GzDecoder;
+    use std::io::Read;
+
+    fn gunzip(body: &[u8]) -> Vec<u8> {
+        let mut decoded = Vec::new();
+        GzDecoder::new(body).read_to_end(&mut decoded).unwrap();
+        decoded
+    }
+
+    #[test]
+    fn below_threshold_is_left_untouched() {
+        let config = CompressionConfig {
+            algorithm: Compression::Gzip,
+            min_bytes: 1024,
+        };
+
+        let (body, encoding) = config.encode(b"small".to_vec()).unwrap();
+
+        assert_eq!(body, b"small");
+        assert_eq!(encoding, None);
+    }
+
+    #[test]
+    fn gzip_round_trips() {
+        let config = CompressionConfig {
+            algorithm: Compression::Gzip,
+            min_bytes: 0,
+        };
+        let payload = "log line ".repeat(200).into_bytes();
+
+        let (body, encoding) = config.encode(payload.clone()).unwrap();
+
+        assert_eq!(encoding, Some("gzip"));
+        assert!(body.len() < payload.len());
+        assert_eq!(gunzip(&body), payload);
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn zstd_round_trips() {
+        let config = CompressionConfig {
+            algorithm: Compression::Zstd,
+            min_bytes: 0,
+        };
+        let payload = "log line ".repeat(200).into_bytes();
+
+        let (body, encoding) = config.encode(payload.clone()).unwrap();
+
+        assert_eq!(encoding, Some("zstd"));
+        assert_eq!(zstd::decode_all(body.as_slice()).unwrap(), payload);
+    }
+}
@@ -0,0 +1,61 @@
+use super::LogtailError;
+use serde::Serialize;
+
+/// Wire format of ingestion request bodies.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Encoding {
+    #[default]
+    Json,
+    /// Smaller and cheaper to produce than JSON on high-volume services.
+    MessagePack,
+}
+
+impl Encoding {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Encoding::Json => "application/json",
+            Encoding::MessagePack => "application/msgpack",
+        }
+    }
+
+    /// Serializes `value`, structs as maps so field names survive in MessagePack too.
+    pub fn encode<T: Serialize + ?Sized>(&self, value: &T) -> Result<Vec<u8>, LogtailError> {
+        match self {
+            Encoding::Json => Ok(serde_json::to_vec(value)?),
+            Encoding::MessagePack => Ok(rmp_serde::to_vec_named(value)?),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+
+    #[test]
+    fn content_types() {
+        assert_eq!(Encoding::Json.content_type(), "application/json");
+        assert_eq!(Encoding::MessagePack.content_type(), "application/msgpack");
+    }
+
+    #[test]
+    fn default_is_json() {
+        assert_eq!(Encoding::default(), Encoding::Json);
+    }
+
+    #[test]
+    fn json_encodes_compact_json() {
+        let bytes = Encoding::Json.encode(&json!({"message": "hi"})).unwrap();
+        assert_eq!(bytes, br#"{"message":"hi"}"#);
+    }
+
+    #[test]
+    fn msgpack_round_trips_with_field_names() {
+        let value = json!([{"message": "hi", "level": "Info"}]);
+
+        let bytes = Encoding::MessagePack.encode(&value).unwrap();
+        let decoded: Value = rmp_serde::from_slice(&bytes).unwrap();
+
+        assert_eq!(decoded, value);
+    }
+}
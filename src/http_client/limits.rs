@@ -0,0 +1,310 @@
+use super::{Encoding, LogtailError};
+use crate::r#struct::betterstack_log_schema::BetterStackLogSchema;
+
+/// Room left for the truncation marker when a record has to shrink to fit.
+const MARKER_SLACK: usize = 32;
+
+/// Upper bounds, in bytes, on what is shipped. Longer strings are cut and end with
+/// `…[truncated N bytes]`, batches are split into several requests.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SizeLimits {
+    pub max_message_bytes: usize,
+    pub max_context_bytes: usize,
+    /// Any other string field, such as `app_version`, a label value, a host detail or an
+    /// error source. `error.message` counts as a message. Label keys are never cut, so
+    /// distinct labels stay distinct.
+    pub max_field_bytes: usize,
+    /// `error.backtrace`, which needs more room than other fields to stay useful.
+    pub max_backtrace_bytes: usize,
+    /// One encoded record; its message is cut further when the record is still larger.
+    pub max_record_bytes: usize,
+    /// One encoded request of several records.
+    pub max_batch_bytes: usize,
+}
+
+impl Default for SizeLimits {
+    fn default() -> Self {
+        Self {
+            max_message_bytes: 64 * 1024,
+            max_context_bytes: 8 * 1024,
+            max_field_bytes: 1024,
+            max_backtrace_bytes: 32 * 1024,
+            max_record_bytes: 1024 * 1024,
+            max_batch_bytes: 10 * 1024 * 1024,
+        }
+    }
+}
+
+impl SizeLimits {
+    /// A copy of `log` within these limits once encoded with `encoding`.
+    pub fn limit(
+        &self,
+        log: &BetterStackLogSchema,
+        encoding: Encoding,
+    ) -> Result<BetterStackLogSchema, LogtailError> {
+        let mut limited = log.clone();
+        limited.message = truncate(&log.message, self.max_message_bytes);
+        limited.context = truncate(&log.context, self.max_context_bytes);
+        self.limit_fields(&mut limited);
+
+        let mut keep = log.message.len().min(self.max_message_bytes);
+        let mut size = encoding.encode(&limited)?.len();
+        while size > self.max_record_bytes && keep > 0 {
+            keep = keep.saturating_sub(size - self.max_record_bytes + MARKER_SLACK);
+            limited.message = truncate(&log.message, keep);
+            size = encoding.encode(&limited)?.len();
+        }
+        if size > self.max_record_bytes {
+            return Err(LogtailError::TooLarge {
+                size,
+                limit: self.max_record_bytes,
+            });
+        }
+        Ok(limited)
+    }
+
+    /// Truncates every string field but `message`, `context` and the label keys.
+    fn limit_fields(&self, log: &mut BetterStackLogSchema) {
+        let field = |text: &mut String| *text = truncate(text, self.max_field_bytes);
+        field(&mut log.app_version);
+        if let Some(service) = &mut log.service {
+            field(service);
+        }
+        log.labels.values_mut().for_each(field);
+        if let Some(error) = &mut log.error {
+            error.message = truncate(&error.message, self.max_message_bytes);
+            error.sources.iter_mut().for_each(field);
+            if let Some(backtrace) = &mut error.backtrace {
+                *backtrace = truncate(backtrace, self.max_backtrace_bytes);
+            }
+        }
+        if let Some(host) = &mut log.host {
+            [
+                &mut host.hostname,
+                &mut host.process_name,
+                &mut host.os,
+                &mut host.arch,
+                &mut host.pod_name,
+                &mut host.pod_namespace,
+                &mut host.node_name,
+                &mut host.container_name,
+            ]
+            .into_iter()
+            .flatten()
+            .for_each(field);
+        }
+        if let Some(name) = log.thread.as_mut().and_then(|thread| thread.name.as_mut()) {
+            field(name);
+        }
+    }
+
+    /// Consecutive runs of `logs` whose encoded array fits `max_batch_bytes`. A record
+    /// larger than that on its own is sent alone.
+    pub fn split<'a>(
+        &self,
+        logs: &'a [BetterStackLogSchema],
+        encoding: Encoding,
+    ) -> Result<Vec<&'a [BetterStackLogSchema]>, LogtailError> {
+        let mut batches = Vec::new();
+        let mut start = 0;
+        // array delimiters, then one separator per record
+        let mut size = 2;
+        for (i, log) in logs.iter().enumerate() {
+            let record = encoding.encode(log)?.len() + 1;
+            if i > start && size + record > self.max_batch_bytes {
+                batches.push(&logs[start..i]);
+                start = i;
+                size = 2;
+            }
+            size += record;
+        }
+        if start < logs.len() {
+            batches.push(&logs[start..]);
+        }
+        Ok(batches)
+    }
+}
+
+/// The first `keep` bytes of `text`, backed off to a char boundary, plus the marker.
+fn truncate(text: &str, keep: usize) -> String {
+    if text.len() <= keep {
+        return text.to_string();
+    }
+    let mut end = keep;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}…[truncated {} bytes]", &text[..end], text.len() - end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::r#struct::error_details::ErrorDetails;
+    use crate::r#struct::log_level::LogLevel;
+    use crate::r#struct::metadata_config::HostMetadata;
+
+    fn log(message: &str) -> BetterStackLogSchema {
+        BetterStackLogSchema::fixture(LogLevel::Info, message)
+    }
+
+    #[test]
+    fn truncate_keeps_short_text() {
+        assert_eq!(truncate("short", 10), "short");
+    }
+
+    #[test]
+    fn truncate_appends_marker() {
+        assert_eq!(truncate("abcdefghij", 4), "abcd…[truncated 6 bytes]");
+    }
+
+    #[test]
+    fn truncate_respects_char_boundaries() {
+        // "é" is two bytes, cutting after one would split it
+        assert_eq!(truncate("aéb", 2), "a…[truncated 3 bytes]");
+    }
+
+    #[test]
+    fn limit_truncates_each_field() {
+        let limits = SizeLimits {
+            max_message_bytes: 4,
+            max_context_bytes: 2,
+            max_field_bytes: 1,
+            ..SizeLimits::default()
+        };
+
+        let limited = limits.limit(&log("hello world"), Encoding::Json).unwrap();
+
+        assert_eq!(limited.message, "hell…[truncated 7 bytes]");
+        assert_eq!(limited.context, "ct…[truncated 1 bytes]");
+        assert_eq!(limited.app_version, "1…[truncated 4 bytes]");
+    }
+
+    #[test]
+    fn limit_truncates_nested_fields() {
+        let limits = SizeLimits {
+            max_message_bytes: 4,
+            max_field_bytes: 3,
+            max_backtrace_bytes: 5,
+            ..SizeLimits::default()
+        };
+        let log = BetterStackLogSchema {
+            service: Some("billing".to_string()),
+            labels: [("region".to_string(), "eu-west-1".to_string())].into(),
+            error: Some(ErrorDetails {
+                message: "disk full".to_string(),
+                sources: vec!["os error 28".to_string()],
+                backtrace: Some("0: main".to_string()),
+            }),
+            host: Some(HostMetadata {
+                hostname: Some("web-01".to_string()),
+                pod_name: Some("api-7d9f".to_string()),
+                ..HostMetadata::default()
+            }),
+            ..log("hi")
+        };
+
+        let limited = limits.limit(&log, Encoding::Json).unwrap();
+
+        assert_eq!(limited.service.as_deref(), Some("bil…[truncated 4 bytes]"));
+        assert_eq!(limited.labels["region"], "eu-…[truncated 6 bytes]");
+        let error = limited.error.unwrap();
+        assert_eq!(error.message, "disk…[truncated 5 bytes]");
+        assert_eq!(error.sources, vec!["os …[truncated 8 bytes]"]);
+        assert_eq!(
+            error.backtrace.as_deref(),
+            Some("0: ma…[truncated 2 bytes]")
+        );
+        let host = limited.host.unwrap();
+        assert_eq!(host.hostname.as_deref(), Some("web…[truncated 3 bytes]"));
+        assert_eq!(host.pod_name.as_deref(), Some("api…[truncated 5 bytes]"));
+    }
+
+    #[test]
+    fn limit_keeps_default_sized_backtraces() {
+        let backtrace = "   0: app::handler\n             at src/handler.rs:42:9\n".repeat(100);
+        let log = BetterStackLogSchema {
+            error: Some(ErrorDetails {
+                message: "boom".to_string(),
+                sources: Vec::new(),
+                backtrace: Some(backtrace.clone()),
+            }),
+            ..log("hi")
+        };
+
+        let limited = SizeLimits::default().limit(&log, Encoding::Json).unwrap();
+
+        assert_eq!(limited.error.unwrap().backtrace, Some(backtrace));
+    }
+
+    #[test]
+    fn limit_shrinks_message_to_fit_record() {
+        let limits = SizeLimits {
+            max_record_bytes: 300,
+            ..SizeLimits::default()
+        };
+
+        let limited = limits
+            .limit(&log(&"x".repeat(5000)), Encoding::Json)
+            .unwrap();
+
+        assert!(Encoding::Json.encode(&limited).unwrap().len() <= 300);
+        assert!(limited.message.ends_with(" bytes]"));
+    }
+
+    #[test]
+    fn limit_fails_when_record_cannot_fit() {
+        let limits = SizeLimits {
+            max_record_bytes: 10,
+            ..SizeLimits::default()
+        };
+
+        let result = limits.limit(&log("hi"), Encoding::Json);
+
+        assert!(matches!(
+            result,
+            Err(LogtailError::TooLarge { limit: 10, .. })
+        ));
+    }
+
+    #[test]
+    fn split_keeps_small_batches_whole() {
+        let logs = vec![log("a"), log("b"), log("c")];
+
+        let batches = SizeLimits::default().split(&logs, Encoding::Json).unwrap();
+
+        assert_eq!(batches.len(), 1);
+        assert_eq!(batches[0].len(), 3);
+    }
+
+    #[test]
+    fn split_fits_each_batch_in_limit() {
+        let logs: Vec<_> = (0..10).map(|i| log(&format!("message {}", i))).collect();
+        let limits = SizeLimits {
+            max_batch_bytes: 400,
+            ..SizeLimits::default()
+        };
+
+        let batches = limits.split(&logs, Encoding::Json).unwrap();
+
+        assert!(batches.len() > 1);
+        assert_eq!(batches.iter().map(|batch| batch.len()).sum::<usize>(), 10);
+        for batch in batches {
+            assert!(Encoding::Json.encode(batch).unwrap().len() <= 400);
+        }
+    }
+
+    #[test]
+    fn split_isolates_oversized_record() {
+        let logs = vec![log("a"), log(&"x".repeat(1000)), log("b")];
+        let limits = SizeLimits {
+            max_batch_bytes: 500,
+            ..SizeLimits::default()
+        };
+
+        let batches = limits.split(&logs, Encoding::Json).unwrap();
+
+        let lens: Vec<_> = batches.iter().map(|batch| batch.len()).collect();
+        assert_eq!(lens, vec![1, 1, 1]);
+    }
+}
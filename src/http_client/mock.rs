@@ -1,57 +1,71 @@
-use super::{HttpClient, LogtailError};
-use reqwest::header::HeaderMap;
+use super::{Encoding, HttpClient, HttpRequest, HttpResponse, LogtailError};
 use serde_json::Value;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Mutex;
 
 pub(crate) struct MockHttpClient {
-    result: Mutex<Result<Option<Value>, String>>,
+    response: HttpResponse,
+    encoding: Encoding,
     pub captured_url: Mutex<Option<String>>,
+    /// The last body decoded as JSON, `None` for other encodings.
     pub captured_body: Mutex<Option<Value>>,
-    pub captured_headers: Mutex<Option<HeaderMap>>,
+    pub captured_request: Mutex<Option<HttpRequest>>,
     pub call_count: AtomicUsize,
 }
 
 impl MockHttpClient {
     pub fn with_success(response: Option<Value>) -> Self {
-        Self {
-            result: Mutex::new(Ok(response)),
-            captured_url: Mutex::new(None),
-            captured_body: Mutex::new(None),
-            captured_headers: Mutex::new(None),
-            call_count: AtomicUsize::new(0),
-        }
+        let body = response
+            .map(|value| serde_json::to_vec(&value).unwrap())
+            .unwrap_or_default();
+        Self::with_response(HttpResponse {
+            status: 200,
+            headers: Vec::new(),
+            body,
+        })
     }
 
     pub fn with_error(message: &str) -> Self {
+        Self::with_response(HttpResponse {
+            status: 500,
+            headers: Vec::new(),
+            body: message.as_bytes().to_vec(),
+        })
+    }
+
+    pub fn with_response(response: HttpResponse) -> Self {
         Self {
-            result: Mutex::new(Err(message.to_string())),
+            response,
+            encoding: Encoding::Json,
             captured_url: Mutex::new(None),
             captured_body: Mutex::new(None),
-            captured_headers: Mutex::new(None),
+            captured_request: Mutex::new(None),
             call_count: AtomicUsize::new(0),
         }
     }
+
+    pub fn with_encoding(mut self, encoding: Encoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
+    pub fn captured_bytes(&self) -> Vec<u8> {
+        let request = self.captured_request.lock().unwrap();
+        request.as_ref().unwrap().body.clone()
+    }
 }
 
 impl HttpClient for MockHttpClient {
-    async fn post_json(
-        &self,
-        url: &str,
-        body: &Value,
-        extra_headers: Option<HeaderMap>,
-    ) -> Result<Option<Value>, LogtailError> {
+    async fn send(&self, request: HttpRequest) -> Result<HttpResponse, LogtailError> {
         self.call_count.fetch_add(1, Ordering::SeqCst);
-        *self.captured_url.lock().unwrap() = Some(url.to_string());
-        *self.captured_body.lock().unwrap() = Some(body.clone());
-        *self.captured_headers.lock().unwrap() = extra_headers;
-
-        match &*self.result.lock().unwrap() {
-            Ok(val) => Ok(val.clone()),
-            Err(msg) => Err(LogtailError::Http {
-                status: 500,
-                message: msg.clone(),
-            }),
-        }
+        *self.captured_url.lock().unwrap() = Some(request.url.clone());
+        *self.captured_body.lock().unwrap() = serde_json::from_slice(&request.body).ok();
+        *self.captured_request.lock().unwrap() = Some(request);
+
+        Ok(self.response.clone())
+    }
+
+    fn encoding(&self) -> Encoding {
+        self.encoding
     }
 }
@@ -1,11 +1,25 @@
 mod base_client;
+mod circuit_breaker;
+#[cfg(feature = "compression")]
+mod compression;
+mod encoding;
+mod limits;
 pub mod service;
 
-pub use base_client::ReqwestClient;
+pub use base_client::{ReqwestClient, Timeouts};
+pub use circuit_breaker::{CircuitBreaker, CircuitBreakerConfig, CircuitState};
+#[cfg(feature = "compression")]
+pub use compression::{Compression, CompressionConfig};
+pub use encoding::Encoding;
+pub use limits::SizeLimits;
 
-use reqwest::header::HeaderMap;
-use serde_json::Value;
 use std::future::Future;
+use std::sync::Arc;
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Receives every failure the logger swallows, see `Logger::with_error_hook`.
+pub type ErrorHook = Arc<dyn Fn(&LogtailError) + Send + Sync>;
 
 #[derive(Debug, thiserror::Error)]
 pub enum LogtailError {
@@ -13,18 +27,180 @@ pub enum LogtailError {
     Http { status: u16, message: String },
     #[error("serialization failed: {0}")]
     Serialization(#[from] serde_json::Error),
+    #[error("msgpack serialization failed: {0}")]
+    MessagePack(#[from] rmp_serde::encode::Error),
+    /// The transport could not complete the request, whatever library it is built on.
     #[error("network error: {0}")]
-    Network(#[from] reqwest::Error),
+    Network(BoxError),
+    /// A request, or the whole delivery including retries, ran out of time.
+    #[error("timed out")]
+    Timeout,
+    /// A record is still larger than `SizeLimits::max_record_bytes` after truncation.
+    #[error("record of {size} bytes exceeds the {limit} bytes limit")]
+    TooLarge { size: usize, limit: usize },
+    /// The circuit breaker changed state, reported rather than returned.
+    #[error("circuit breaker {0}")]
+    Circuit(CircuitState),
+    #[error("invalid header: {0}")]
+    InvalidHeader(String),
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+impl From<reqwest::Error> for LogtailError {
+    fn from(err: reqwest::Error) -> Self {
+        if err.is_timeout() {
+            LogtailError::Timeout
+        } else {
+            LogtailError::Network(Box::new(err))
+        }
+    }
+}
+
+impl LogtailError {
+    /// Whether trying again later may succeed: network failures, timeouts, `429` and `5xx`.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            LogtailError::Http { status, .. } => *status == 429 || *status >= 500,
+            LogtailError::Network(_) | LogtailError::Timeout => true,
+            _ => false,
+        }
+    }
+}
+
+/// An encoded request body and where to send it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HttpRequest {
+    pub url: String,
+    pub body: Vec<u8>,
+    pub content_type: String,
+    /// Set when `body` is already compressed, e.g. `gzip`.
+    pub content_encoding: Option<String>,
+    /// Additional headers, such as `Authorization`.
+    pub headers: Vec<(String, String)>,
+}
+
+impl HttpRequest {
+    /// Value of the first additional header named `name`, ignoring case.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        find_header(&self.headers, name)
+    }
+}
+
+/// What the server answered, whatever its status.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct HttpResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl HttpResponse {
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+
+    /// Value of the first header named `name`, ignoring case.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        find_header(&self.headers, name)
+    }
 }
 
+fn find_header<'a>(headers: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    headers
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case(name))
+        .map(|(_, value)| value.as_str())
+}
+
+/// Moves encoded bodies to an ingestion endpoint.
+///
+/// Implementations only deal in bytes and plain header pairs, so the crate can ship
+/// through reqwest ([`ReqwestClient`]), hyper, ureq or an in-process fake alike.
+/// Non-success statuses are returned as responses, not errors.
 pub trait HttpClient: Send + Sync {
-    fn post_json(
+    fn send(
         &self,
-        url: &str,
-        body: &Value,
-        extra_headers: Option<HeaderMap>,
-    ) -> impl Future<Output = Result<Option<Value>, LogtailError>> + Send;
+        request: HttpRequest,
+    ) -> impl Future<Output = Result<HttpResponse, LogtailError>> + Send;
+
+    /// Encoding `push_log` serializes records with before calling [`HttpClient::send`].
+    fn encoding(&self) -> Encoding {
+        Encoding::Json
+    }
+
+    /// A copy of this client that keeps no connections between requests, for the panic
+    /// hook, which sends from a runtime of its own where pooled connections cannot be
+    /// reused. `None`, the default, has the hook send through this client as it is.
+    fn unpooled(&self) -> Option<Self>
+    where
+        Self: Sized,
+    {
+        None
+    }
+}
+
+impl<T: HttpClient> HttpClient for Arc<T> {
+    fn send(
+        &self,
+        request: HttpRequest,
+    ) -> impl Future<Output = Result<HttpResponse, LogtailError>> + Send {
+        (**self).send(request)
+    }
+
+    fn encoding(&self) -> Encoding {
+        (**self).encoding()
+    }
+
+    fn unpooled(&self) -> Option<Self> {
+        (**self).unpooled().map(Arc::new)
+    }
 }
 
 #[cfg(test)]
 pub(crate) mod mock;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn response_success_range() {
+        let status = |status| HttpResponse {
+            status,
+            ..HttpResponse::default()
+        };
+        assert!(status(200).is_success());
+        assert!(status(202).is_success());
+        assert!(!status(199).is_success());
+        assert!(!status(301).is_success());
+        assert!(!status(500).is_success());
+    }
+
+    #[test]
+    fn retryable_errors() {
+        let http = |status| LogtailError::Http {
+            status,
+            message: String::new(),
+        };
+        assert!(http(429).is_retryable());
+        assert!(http(500).is_retryable());
+        assert!(http(503).is_retryable());
+        assert!(!http(400).is_retryable());
+        assert!(!http(401).is_retryable());
+        assert!(LogtailError::Timeout.is_retryable());
+        assert!(LogtailError::Network("reset".into()).is_retryable());
+        assert!(!LogtailError::InvalidHeader("x".to_string()).is_retryable());
+    }
+
+    #[test]
+    fn header_lookup_ignores_case() {
+        let response = HttpResponse {
+            status: 200,
+            headers: vec![("Retry-After".to_string(), "5".to_string())],
+            body: Vec::new(),
+        };
+        assert_eq!(response.header("retry-after"), Some("5"));
+        assert_eq!(response.header("missing"), None);
+    }
+}
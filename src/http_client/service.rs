@@ -1,7 +1,8 @@
-use super::HttpClient;
+use super::{HttpClient, HttpRequest, HttpResponse, LogtailError};
 use crate::r#struct::betterstack_log_schema::BetterStackLogSchema;
+use crate::r#struct::delivery_config::DeliveryConfig;
 use crate::r#struct::env_config::EnvConfig;
-use reqwest::header::{HeaderMap, HeaderValue};
+use serde::Serialize;
 use serde_json::Value;
 
 /// Pushes a log to the BetterStack logs server asynchronously and returns a value.
@@ -21,11 +22,7 @@ pub async fn push_log(
     config: &EnvConfig,
     log: &BetterStackLogSchema,
 ) -> Option<Value> {
-    let logs_url = "https://in.logs.betterstack.com";
-    let bearer_header = bearer_headers(config);
-    let body = serde_json::to_value(log).expect("Failed to serialize log to JSON");
-
-    let http_result = client.post_json(logs_url, &body, Some(bearer_header)).await;
+    let http_result = send_log(client, config, log).await;
 
     match http_result {
         Err(err) => {
@@ -38,40 +35,193 @@ pub async fn push_log(
     }
 }
 
+/// Pushes a log to the BetterStack logs server, surfacing any failure to the caller.
+///
+/// The body is serialized with the client's [`HttpClient::encoding`].
+///
+/// # Returns
+///
+/// * `Ok` with the continuation value, if the server returned one.
+/// * `Err` if the log could not be serialized or the request failed.
+pub async fn send_log(
+    client: &impl HttpClient,
+    config: &EnvConfig,
+    log: &BetterStackLogSchema,
+) -> Result<Option<Value>, LogtailError> {
+    let log = config.delivery.size_limits.limit(log, client.encoding())?;
+    post_encoded(client, config, &log).await
+}
+
+/// Pushes several logs as array bodies, split into as many requests as
+/// `config.delivery.size_limits` requires.
+///
+/// A record that cannot be made to fit, or a failed request, does not stop the others:
+/// the first such error is returned once everything else has been sent.
+pub async fn send_logs(
+    client: &impl HttpClient,
+    config: &EnvConfig,
+    logs: &[BetterStackLogSchema],
+) -> Result<Option<Value>, LogtailError> {
+    let report = send_batches(client, config, logs).await;
+    match report.error {
+        Some(err) => Err(err),
+        None => Ok(report.continuation),
+    }
+}
+
+/// How [`send_batches`] went.
+pub(crate) struct BatchReport {
+    pub(crate) continuation: Option<Value>,
+    /// The first failure, if any.
+    pub(crate) error: Option<LogtailError>,
+    /// Records of the requests that failed with a retryable error, worth sending again.
+    /// Records that were delivered or rejected for good are not in here.
+    pub(crate) retryable: Vec<BetterStackLogSchema>,
+}
+
+/// [`send_logs`], telling which records are worth sending again.
+pub(crate) async fn send_batches(
+    client: &impl HttpClient,
+    config: &EnvConfig,
+    logs: &[BetterStackLogSchema],
+) -> BatchReport {
+    let limits = &config.delivery.size_limits;
+    let encoding = client.encoding();
+    let mut report = BatchReport {
+        continuation: None,
+        error: None,
+        retryable: Vec::new(),
+    };
+    let mut limited = Vec::with_capacity(logs.len());
+    for log in logs {
+        match limits.limit(log, encoding) {
+            Ok(log) => limited.push(log),
+            Err(err) => {
+                report.error.get_or_insert(err);
+            }
+        }
+    }
+
+    let batches = match limits.split(&limited, encoding) {
+        Ok(batches) => batches,
+        Err(err) => {
+            report.error.get_or_insert(err);
+            return report;
+        }
+    };
+    for batch in batches {
+        match post_encoded(client, config, batch).await {
+            Ok(value) => report.continuation = value,
+            Err(err) => {
+                if err.is_retryable() {
+                    report.retryable.extend_from_slice(batch);
+                }
+                report.error.get_or_insert(err);
+            }
+        }
+    }
+    report
+}
+
+/// Sends the payload, retrying per `config.delivery` within its total deadline.
+async fn post_encoded<T: Serialize + ?Sized>(
+    client: &impl HttpClient,
+    config: &EnvConfig,
+    payload: &T,
+) -> Result<Option<Value>, LogtailError> {
+    let encoding = client.encoding();
+    let request = HttpRequest {
+        url: config.logs_url.clone(),
+        body: encoding.encode(payload)?,
+        content_type: encoding.content_type().to_string(),
+        content_encoding: None,
+        headers: bearer_headers(config),
+    };
+
+    continuation_value(deliver(client, &config.delivery, request).await?)
+}
+
+/// Sends `request`, retrying per `delivery` within its total deadline, and turns a
+/// non-success final response into an error.
+pub(crate) async fn deliver(
+    client: &impl HttpClient,
+    delivery: &DeliveryConfig,
+    request: HttpRequest,
+) -> Result<HttpResponse, LogtailError> {
+    let attempts = send_with_retries(client, delivery, request);
+    match delivery.total_timeout {
+        Some(deadline) => tokio::time::timeout(deadline, attempts)
+            .await
+            .map_err(|_| LogtailError::Timeout)?,
+        None => attempts.await,
+    }
+}
+
+async fn send_with_retries(
+    client: &impl HttpClient,
+    delivery: &DeliveryConfig,
+    request: HttpRequest,
+) -> Result<HttpResponse, LogtailError> {
+    let mut retry = 0;
+    loop {
+        let result = match client.send(request.clone()).await {
+            Ok(response) => check_status(response),
+            Err(err) => Err(err),
+        };
+        match result {
+            Err(err) if err.is_retryable() && retry < delivery.retries => {
+                retry += 1;
+                tokio::time::sleep(delivery.backoff(retry)).await;
+            }
+            result => return result,
+        }
+    }
+}
+
 /// Generate a bearer header for the given server configuration.
 ///
 /// # Parameters
 /// - `server_config`: A reference to the server configuration.
 ///
 /// # Returns
-/// The generated bearer header as a `HeaderMap`.
+/// The generated bearer header as a list of header pairs.
 ///
-fn bearer_headers(config: &EnvConfig) -> HeaderMap {
+fn bearer_headers(config: &EnvConfig) -> Vec<(String, String)> {
     let logs_source_token = config.logs_source_token.as_str();
-    let bearer_value_str = format!("Bearer {}", logs_source_token);
-    let bearer_value = &bearer_value_str;
+    let bearer_value = format!("Bearer {}", logs_source_token);
 
-    let mut headers = HeaderMap::new();
+    vec![("Authorization".to_string(), bearer_value)]
+}
 
-    headers.insert(
-        "Authorization",
-        HeaderValue::from_str(bearer_value).unwrap(),
-    );
-    headers.insert(
-        "Content-Type",
-        HeaderValue::from_str("application/json").unwrap(),
-    );
+fn check_status(response: HttpResponse) -> Result<HttpResponse, LogtailError> {
+    if !response.is_success() {
+        let status = response.status;
+        return Err(LogtailError::Http {
+            status,
+            message: format!("HTTP request failed with status {}", status),
+        });
+    }
+    Ok(response)
+}
 
-    headers
+/// Parses the JSON body of a successful response.
+fn continuation_value(response: HttpResponse) -> Result<Option<Value>, LogtailError> {
+    if response.body.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(serde_json::from_slice(&response.body)?))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::http_client::mock::MockHttpClient;
+    use crate::http_client::Encoding;
     use crate::r#struct::env_config::{EnvConfig, EnvEnum};
     use crate::r#struct::log_level::LogLevel;
+    use crate::test_util::{RecordingTransport, ScriptedResponse};
     use std::sync::atomic::Ordering;
+    use std::time::Duration;
 
     fn test_config() -> EnvConfig {
         EnvConfig::from_values(
@@ -84,11 +234,8 @@ mod tests {
 
     fn test_log() -> BetterStackLogSchema {
         BetterStackLogSchema {
-            env: EnvEnum::QA,
-            message: "test message".to_string(),
             context: "test context".to_string(),
-            level: LogLevel::Info,
-            app_version: "1.0.0".to_string(),
+            ..BetterStackLogSchema::fixture(LogLevel::Info, "test message")
         }
     }
 
@@ -101,14 +248,26 @@ mod tests {
         assert_eq!(url, "https://in.logs.betterstack.com");
     }
 
+    #[tokio::test]
+    async fn calls_configured_url() {
+        let mock = MockHttpClient::with_success(None);
+        let mut config = test_config();
+        config.logs_url = "https://s1234.eu-nbg-2.betterstackdata.com".to_string();
+
+        push_log(&mock, &config, &test_log()).await;
+
+        let url = mock.captured_url.lock().unwrap().clone().unwrap();
+        assert_eq!(url, "https://s1234.eu-nbg-2.betterstackdata.com");
+    }
+
     #[tokio::test]
     async fn sends_bearer_header() {
         let mock = MockHttpClient::with_success(None);
         push_log(&mock, &test_config(), &test_log()).await;
 
-        let headers = mock.captured_headers.lock().unwrap().clone().unwrap();
+        let request = mock.captured_request.lock().unwrap().clone().unwrap();
         assert_eq!(
-            headers.get("Authorization").unwrap().to_str().unwrap(),
+            request.header("Authorization").unwrap(),
             "Bearer test-source-token"
         );
     }
@@ -118,11 +277,9 @@ mod tests {
         let mock = MockHttpClient::with_success(None);
         push_log(&mock, &test_config(), &test_log()).await;
 
-        let headers = mock.captured_headers.lock().unwrap().clone().unwrap();
-        assert_eq!(
-            headers.get("Content-Type").unwrap().to_str().unwrap(),
-            "application/json"
-        );
+        let request = mock.captured_request.lock().unwrap().clone().unwrap();
+        assert_eq!(request.content_type, "application/json");
+        assert_eq!(request.content_encoding, None);
     }
 
     #[tokio::test]
@@ -138,6 +295,49 @@ mod tests {
         assert_eq!(body["app_version"], "1.0.0");
     }
 
+    #[tokio::test]
+    async fn sends_msgpack_when_client_encoding_is_msgpack() {
+        let mock = MockHttpClient::with_success(None).with_encoding(Encoding::MessagePack);
+        push_log(&mock, &test_config(), &test_log()).await;
+
+        let request = mock.captured_request.lock().unwrap().clone().unwrap();
+        assert_eq!(request.content_type, "application/msgpack");
+        let bytes = mock.captured_bytes();
+        let body: Value = rmp_serde::from_slice(&bytes).unwrap();
+        assert_eq!(body["message"], "test message");
+        assert_eq!(body["level"], "Info");
+    }
+
+    #[tokio::test]
+    async fn send_logs_sends_array_body() {
+        let mock = MockHttpClient::with_success(None);
+        let mut second = test_log();
+        second.message = "second".to_string();
+
+        send_logs(&mock, &test_config(), &[test_log(), second])
+            .await
+            .unwrap();
+
+        let body = mock.captured_body.lock().unwrap().clone().unwrap();
+        assert_eq!(body.as_array().unwrap().len(), 2);
+        assert_eq!(body[0]["message"], "test message");
+        assert_eq!(body[1]["message"], "second");
+    }
+
+    #[tokio::test]
+    async fn send_logs_msgpack_array() {
+        let mock = MockHttpClient::with_success(None).with_encoding(Encoding::MessagePack);
+
+        send_logs(&mock, &test_config(), &[test_log(), test_log()])
+            .await
+            .unwrap();
+
+        let bytes = mock.captured_bytes();
+        let body: Value = rmp_serde::from_slice(&bytes).unwrap();
+        assert_eq!(body.as_array().unwrap().len(), 2);
+        assert_eq!(body[1]["env"], "QA");
+    }
+
     #[tokio::test]
     async fn returns_some_on_success() {
         let response = serde_json::json!({"status": "ok"});
@@ -155,6 +355,110 @@ mod tests {
         assert!(result.is_none());
     }
 
+    #[tokio::test]
+    async fn send_log_surfaces_http_error() {
+        let mock = MockHttpClient::with_error("connection refused");
+
+        let result = send_log(&mock, &test_config(), &test_log()).await;
+        assert!(matches!(
+            result,
+            Err(LogtailError::Http { status: 500, .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn send_log_maps_status_to_http_error() {
+        let mock = MockHttpClient::with_response(HttpResponse {
+            status: 429,
+            ..HttpResponse::default()
+        });
+
+        let result = send_log(&mock, &test_config(), &test_log()).await;
+        assert!(matches!(
+            result,
+            Err(LogtailError::Http { status: 429, .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn send_log_fails_on_invalid_json_response() {
+        let mock = MockHttpClient::with_response(HttpResponse {
+            status: 200,
+            headers: Vec::new(),
+            body: b"not json".to_vec(),
+        });
+
+        let result = send_log(&mock, &test_config(), &test_log()).await;
+        assert!(matches!(result, Err(LogtailError::Serialization(_))));
+    }
+
+    fn retrying_config(retries: u32, total_timeout: Option<Duration>) -> EnvConfig {
+        let mut config = test_config();
+        config.delivery = DeliveryConfig {
+            retries,
+            retry_backoff: Duration::from_millis(1),
+            total_timeout,
+            circuit_breaker: None,
+            ..DeliveryConfig::default()
+        };
+        config
+    }
+
+    #[tokio::test]
+    async fn retries_retryable_failures() {
+        let transport = RecordingTransport::new();
+        transport
+            .then_respond(ScriptedResponse::Status(503))
+            .then_respond(ScriptedResponse::NetworkError("reset".to_string()));
+
+        let result = send_log(&transport, &retrying_config(2, None), &test_log()).await;
+
+        assert!(result.is_ok());
+        assert_eq!(transport.request_count(), 3);
+    }
+
+    #[tokio::test]
+    async fn gives_up_after_configured_retries() {
+        let transport = RecordingTransport::new();
+        transport.always_respond(ScriptedResponse::Status(500));
+
+        let result = send_log(&transport, &retrying_config(2, None), &test_log()).await;
+
+        assert!(matches!(
+            result,
+            Err(LogtailError::Http { status: 500, .. })
+        ));
+        assert_eq!(transport.request_count(), 3);
+    }
+
+    #[tokio::test]
+    async fn does_not_retry_client_errors() {
+        let transport = RecordingTransport::new();
+        transport.always_respond(ScriptedResponse::Status(401));
+
+        let result = send_log(&transport, &retrying_config(3, None), &test_log()).await;
+
+        assert!(matches!(
+            result,
+            Err(LogtailError::Http { status: 401, .. })
+        ));
+        assert_eq!(transport.request_count(), 1);
+    }
+
+    #[tokio::test]
+    async fn total_timeout_covers_retries() {
+        let transport = RecordingTransport::new();
+        transport.always_respond(ScriptedResponse::Status(500).delayed(Duration::from_millis(30)));
+        let config = retrying_config(10, Some(Duration::from_millis(100)));
+
+        let started = std::time::Instant::now();
+        let result = send_log(&transport, &config, &test_log()).await;
+
+        assert!(matches!(result, Err(LogtailError::Timeout)));
+        assert!(started.elapsed() < Duration::from_secs(1));
+        assert!(transport.request_count() < 11);
+    }
+
     #[tokio::test]
     async fn returns_none_on_empty_body() {
         let mock = MockHttpClient::with_success(None);
@@ -163,4 +467,86 @@ mod tests {
         assert!(result.is_none());
         assert_eq!(mock.call_count.load(Ordering::SeqCst), 1);
     }
+
+    #[tokio::test]
+    async fn send_log_truncates_oversized_message() {
+        let mut config = test_config();
+        config.delivery.size_limits.max_message_bytes = 5;
+        let mock = MockHttpClient::with_success(None);
+        let log = BetterStackLogSchema {
+            message: "a very long message".to_string(),
+            ..test_log()
+        };
+
+        send_log(&mock, &config, &log).await.unwrap();
+
+        let body = mock.captured_body.lock().unwrap().clone().unwrap();
+        assert_eq!(body["message"], "a ver…[truncated 14 bytes]");
+    }
+
+    #[tokio::test]
+    async fn send_logs_splits_batches() {
+        let mut config = test_config();
+        config.delivery.size_limits.max_batch_bytes = 400;
+        let transport = RecordingTransport::new();
+        let logs: Vec<_> = (0..6)
+            .map(|i| BetterStackLogSchema {
+                message: format!("message {}", i),
+                ..test_log()
+            })
+            .collect();
+
+        send_logs(&transport, &config, &logs).await.unwrap();
+
+        assert!(transport.request_count() > 1);
+        assert_eq!(transport.logs().len(), 6);
+        for request in transport.requests() {
+            assert!(request.body.len() <= 400);
+        }
+    }
+
+    #[tokio::test]
+    async fn send_logs_skips_records_that_cannot_fit() {
+        let mut config = test_config();
+        config.delivery.size_limits.max_record_bytes = 200;
+        config.delivery.size_limits.max_field_bytes = 10_000;
+        let transport = RecordingTransport::new();
+        let oversized = BetterStackLogSchema {
+            app_version: "9".repeat(500),
+            ..test_log()
+        };
+        let logs = vec![test_log(), oversized, test_log()];
+
+        let result = send_logs(&transport, &config, &logs).await;
+
+        assert!(matches!(result, Err(LogtailError::TooLarge { .. })));
+        assert_eq!(transport.logs().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn send_batches_reports_only_retryable_failures() {
+        let mut config = test_config();
+        config.delivery.size_limits.max_batch_bytes = 150;
+        let transport = RecordingTransport::new();
+        transport
+            .then_respond(ScriptedResponse::Success)
+            .then_respond(ScriptedResponse::Status(400))
+            .then_respond(ScriptedResponse::Status(503));
+        let logs: Vec<_> = (0..3)
+            .map(|i| BetterStackLogSchema {
+                message: format!("message {}", i),
+                ..test_log()
+            })
+            .collect();
+
+        let report = send_batches(&transport, &config, &logs).await;
+
+        assert_eq!(transport.request_count(), 3);
+        assert!(matches!(
+            report.error,
+            Some(LogtailError::Http { status: 400, .. })
+        ));
+        let retryable: Vec<_> = report.retryable.iter().map(|log| &log.message).collect();
+        assert_eq!(retryable, vec!["message 2"]);
+    }
 }
@@ -1,89 +1,373 @@
-use crate::http_client::service;
-use crate::http_client::HttpClient;
+use crate::dedup::Dedup;
 use crate::http_client::ReqwestClient;
-use crate::r#struct::env_config::{EnvConfig, EnvEnum};
-use crate::r#struct::log_level::LogLevel;
-// re-export LogSchema to make usable by consumer
+use crate::http_client::{ErrorHook, HttpClient, LogtailError};
+use crate::sink::{BetterStackSink, ConsoleSink, Sink};
+use std::error::Error;
+use std::sync::Arc;
+use std::time::Duration;
+// re-export the record types to make them usable by consumers and custom sinks
+pub use crate::panic_hook::install_panic_hook;
+pub use crate::r#struct::betterstack_log_schema::BetterStackLogSchema;
+pub use crate::r#struct::delivery_config::DeliveryConfig;
+pub use crate::r#struct::env_config::{EnvConfig, EnvEnum};
+pub use crate::r#struct::error_details::ErrorDetails;
+pub use crate::r#struct::log_level::LogLevel;
 pub use crate::r#struct::log_schema::LogSchema;
+pub use crate::r#struct::metadata_config::{HostMetadata, MetadataConfig, ThreadMetadata};
+pub use crate::r#struct::output_config::OutputConfig;
+pub use crate::r#struct::redaction_config::{RedactionConfig, DEFAULT_MASK, DEFAULT_REDACTED_KEYS};
+pub use crate::r#struct::trace_context::TraceContext;
+mod dedup;
 pub mod http_client;
+mod panic_hook;
+pub mod sink;
 mod r#struct;
+#[cfg(any(test, feature = "test-util"))]
+pub mod test_util;
+mod time;
 
 pub struct Logger<C: HttpClient = ReqwestClient> {
     env_config: EnvConfig,
-    client: C,
+    client: Arc<C>,
+    /// Template for the built-in console sink.
+    console: ConsoleSink,
+    /// Shared with the timer writing held back repeats, see [`Logger::with_dedup`].
+    sinks: Arc<Vec<SinkEntry>>,
+    /// The built-in BetterStack sink, also in `sinks`, kept to carry its state over rebuilds.
+    remote: Option<Arc<BetterStackSink<Arc<C>>>>,
+    error_hook: ErrorHook,
+    dedup: Option<Arc<Dedup>>,
+    capture_backtraces: bool,
+    /// Collected from `env_config.metadata` when the logger is built.
+    host: Option<HostMetadata>,
+    trace_context: Option<TraceProvider>,
+}
+
+/// Looks up the trace a record is logged in.
+type TraceProvider = Arc<dyn Fn() -> Option<TraceContext> + Send + Sync>;
+
+/// A registered sink and the lowest level it receives.
+#[derive(Clone)]
+struct SinkEntry {
+    sink: Arc<dyn Sink>,
+    min_level: LogLevel,
+    /// Created from `OutputConfig` rather than registered by the consumer.
+    builtin: bool,
 }
 
 impl Default for Logger<ReqwestClient> {
     fn default() -> Self {
         let env_config = EnvConfig::default();
-        Self {
-            env_config,
-            client: ReqwestClient,
-        }
+        Self::with_client(env_config, ReqwestClient::default())
     }
 }
 
 impl Logger<ReqwestClient> {
     pub fn new(app_version: String, verbose: bool) -> Self {
         let env_config = EnvConfig::new(app_version, verbose);
-        Self {
-            env_config,
-            client: ReqwestClient,
-        }
+        Self::with_client(env_config, ReqwestClient::default())
     }
 }
 
-impl<C: HttpClient> Logger<C> {
-    #[cfg(test)]
-    pub(crate) fn with_client(env_config: EnvConfig, client: C) -> Self {
-        Self { env_config, client }
+impl<C: HttpClient + 'static> Logger<C> {
+    /// Builds a logger shipping through `client`, e.g. a [`ReqwestClient`] with compression.
+    pub fn with_client(env_config: EnvConfig, client: C) -> Self {
+        let mut logger = Self {
+            env_config,
+            client: Arc::new(client),
+            console: ConsoleSink::default(),
+            sinks: Arc::new(Vec::new()),
+            remote: None,
+            error_hook: Arc::new(|err| {
+                // logging errors must not crash the app
+                println!("!!! Error writing log : {}", err);
+            }),
+            dedup: None,
+            capture_backtraces: false,
+            host: None,
+            trace_context: default_trace_provider(),
+        };
+        logger.host = HostMetadata::collect(&logger.env_config.metadata);
+        logger.rebuild_builtin_sinks();
+        logger
     }
 
-    pub async fn info(&self, log: LogSchema) {
-        let env_config = &self.env_config;
-        let better_log = log.to_betterstack(env_config, LogLevel::Info);
-        if better_log.env != EnvEnum::Local {
-            let _result = service::push_log(&self.client, env_config, &better_log).await;
+    /// Overrides where records are written; defaults to [`OutputConfig::from_env`].
+    pub fn with_output(mut self, output: OutputConfig) -> Self {
+        self.env_config.output = output;
+        self.rebuild_builtin_sinks();
+        self
+    }
+
+    /// Replaces what is masked in records before any sink sees them,
+    /// [`RedactionConfig::default`] unless set.
+    pub fn with_redaction(mut self, redaction: RedactionConfig) -> Self {
+        self.env_config.redaction = redaction;
+        self.rebuild_builtin_sinks();
+        self
+    }
+
+    /// Replaces the built-in console sink, e.g. with [`ConsoleSink::pretty`].
+    pub fn with_console(mut self, console: ConsoleSink) -> Self {
+        self.console = console;
+        self.rebuild_builtin_sinks();
+        self
+    }
+
+    /// Registers an additional sink receiving every record at or above `min_level`.
+    pub fn with_sink(mut self, sink: impl Sink + 'static, min_level: LogLevel) -> Self {
+        Arc::make_mut(&mut self.sinks).push(SinkEntry {
+            sink: Arc::new(sink),
+            min_level,
+            builtin: false,
+        });
+        self
+    }
+
+    /// Replaces printing to stdout as the way sink failures and circuit breaker
+    /// transitions are reported.
+    pub fn with_error_hook(mut self, hook: impl Fn(&LogtailError) + Send + Sync + 'static) -> Self {
+        self.error_hook = Arc::new(hook);
+        self.rebuild_builtin_sinks();
+        self
+    }
+
+    /// Collapses records with the same level, message and context logged within `window`:
+    /// the first is written right away, the repeats as one record with `repeat_count`
+    /// once the window has passed.
+    ///
+    /// Inside a tokio runtime a background timer writes the repeats when their window
+    /// expires, otherwise the next record does. Either way, call [`Logger::flush`] before
+    /// shutting down so repeats still held back are not lost.
+    pub fn with_dedup(mut self, window: Duration) -> Self {
+        self.dedup = Some(Arc::new(Dedup::new(window)));
+        self
+    }
+
+    /// Writes the repeats held back by [`Logger::with_dedup`] now, e.g. before shutting down.
+    pub async fn flush_repeats(&self) {
+        let Some(dedup) = &self.dedup else {
+            return;
+        };
+        for log in dedup.flush() {
+            self.write_sinks(&log).await;
         }
-        if env_config.verbose {
-            println!("{}", better_log);
+    }
+
+    /// Writes the held back repeats, then has every sink send what it buffered, e.g.
+    /// before shutting down. Failures go to the error hook.
+    pub async fn flush(&self) {
+        self.flush_repeats().await;
+        for entry in self.sinks.iter() {
+            if let Err(err) = entry.sink.flush().await {
+                (self.error_hook)(&err);
+            }
         }
     }
 
+    /// Selects the host, process and thread details attached to every record,
+    /// [`MetadataConfig::default`] unless set.
+    pub fn with_metadata(mut self, metadata: MetadataConfig) -> Self {
+        self.host = HostMetadata::collect(&metadata);
+        self.env_config.metadata = metadata;
+        self.rebuild_builtin_sinks();
+        self
+    }
+
+    /// Attaches the trace `provider` returns to every record, e.g. one parsed with
+    /// [`TraceContext::from_traceparent`]. With the `opentelemetry` feature, the active
+    /// span is attached unless this is set.
+    pub fn with_trace_context(
+        mut self,
+        provider: impl Fn() -> Option<TraceContext> + Send + Sync + 'static,
+    ) -> Self {
+        self.trace_context = Some(Arc::new(provider));
+        self
+    }
+
+    /// Makes [`Logger::error_with`] capture a backtrace of where it is called.
+    pub fn with_backtraces(mut self, capture_backtraces: bool) -> Self {
+        self.capture_backtraces = capture_backtraces;
+        self
+    }
+
+    pub async fn info(&self, log: LogSchema) {
+        let better_log = self.record(&log, LogLevel::Info);
+        self.dispatch(&better_log).await;
+    }
+
     pub async fn warn(&self, log: LogSchema) {
-        let env_config = &self.env_config;
-        let better_log = log.to_betterstack(&self.env_config, LogLevel::Warn);
-        if better_log.env != EnvEnum::Local {
-            let _result = service::push_log(&self.client, env_config, &better_log).await;
+        let better_log = self.record(&log, LogLevel::Warn);
+        self.dispatch(&better_log).await;
+    }
+
+    pub async fn error(&self, log: LogSchema) {
+        let better_log = self.record(&log, LogLevel::Error);
+        self.dispatch(&better_log).await;
+    }
+
+    /// Logs at `Error` level with `error` attached: its text, its `source()` chain and,
+    /// if enabled with [`Logger::with_backtraces`], a backtrace.
+    pub async fn error_with(&self, error: &dyn Error, log: LogSchema) {
+        let mut better_log = self.record(&log, LogLevel::Error);
+        let details = ErrorDetails::new(error, self.capture_backtraces);
+        better_log.error = Some(details.redacted(&self.env_config.redaction));
+        self.dispatch(&better_log).await;
+    }
+
+    pub async fn debug(&self, log: LogSchema) {
+        let better_log = self.record(&log, LogLevel::Debug);
+        self.dispatch(&better_log).await;
+    }
+
+    /// Builds the shipped record, with metadata attached.
+    fn record(&self, log: &LogSchema, level: LogLevel) -> BetterStackLogSchema {
+        let mut record = log.to_betterstack(&self.env_config, level);
+        record.host = self.host.clone();
+        if self.env_config.metadata.thread {
+            record.thread = Some(ThreadMetadata::current());
         }
-        if self.env_config.verbose {
-            println!("{}", better_log);
+        record.trace = self.trace_context.as_ref().and_then(|provider| provider());
+        record
+    }
+
+    /// Recreates the console and BetterStack sinks selected by `env_config.output`,
+    /// ahead of the sinks registered with [`Logger::with_sink`].
+    ///
+    /// The BetterStack sink keeps the circuit state and spooled records of the one it replaces.
+    fn rebuild_builtin_sinks(&mut self) {
+        Arc::make_mut(&mut self.sinks).retain(|entry| !entry.builtin);
+        let previous = self.remote.take().and_then(Arc::into_inner);
+        self.remote = self.env_config.output.remote.then(|| {
+            let sink = BetterStackSink::new(self.client.clone(), self.env_config.clone())
+                .with_error_hook(self.error_hook.clone());
+            Arc::new(match previous {
+                Some(previous) => sink.with_state_of(previous),
+                None => sink,
+            })
+        });
+        let remote = self.remote.clone().map(|sink| sink as Arc<dyn Sink>);
+        let builtin = self.builtin_sinks(remote);
+        Arc::make_mut(&mut self.sinks).splice(0..0, builtin);
+    }
+
+    /// `remote` and, if `env_config.output` selects it, the console sink.
+    ///
+    /// BetterStack only receives `Info` and above, debug records stay local.
+    fn builtin_sinks(&self, remote: Option<Arc<dyn Sink>>) -> Vec<SinkEntry> {
+        let mut builtin = Vec::new();
+        if let Some(remote) = remote {
+            builtin.push(SinkEntry {
+                sink: remote,
+                min_level: LogLevel::Info,
+                builtin: true,
+            });
+        }
+        if self.env_config.output.console {
+            builtin.push(SinkEntry {
+                sink: Arc::new(self.console.clone()),
+                min_level: LogLevel::Debug,
+                builtin: true,
+            });
         }
+        builtin
     }
 
-    pub async fn error(&self, log: LogSchema) {
-        let env_config = &self.env_config;
-        let better_log = log.to_betterstack(&self.env_config, LogLevel::Error);
-        if better_log.env != EnvEnum::Local {
-            let _result = service::push_log(&self.client, env_config, &better_log).await;
+    async fn dispatch(&self, log: &BetterStackLogSchema) {
+        let Some(dedup) = &self.dedup else {
+            return self.write_sinks(log).await;
+        };
+        for log in dedup.observe(log) {
+            self.write_sinks(&log).await;
         }
-        if self.env_config.verbose {
-            eprintln!("{}", better_log);
+        self.arm_repeat_timer(dedup);
+    }
+
+    /// Spawns the timer writing held back repeats once their window expires, unless one
+    /// is already running or there is no tokio runtime to run it on.
+    fn arm_repeat_timer(&self, dedup: &Arc<Dedup>) {
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+        if !dedup.arm_timer() {
+            return;
         }
+        let dedup = Arc::downgrade(dedup);
+        let sinks = Arc::downgrade(&self.sinks);
+        let error_hook = self.error_hook.clone();
+        runtime.spawn(async move {
+            while let Some(deadline) = dedup.upgrade().and_then(|dedup| dedup.next_expiry()) {
+                tokio::time::sleep_until(deadline.into()).await;
+                let Some(dedup) = dedup.upgrade() else {
+                    return;
+                };
+                let Some(sinks) = sinks.upgrade() else {
+                    // the logger was reconfigured, its next record arms a new timer
+                    dedup.disarm_timer();
+                    return;
+                };
+                for log in dedup.expired() {
+                    write_sinks(&sinks, &error_hook, &log).await;
+                }
+            }
+        });
     }
 
-    pub async fn debug(&self, log: LogSchema) {
-        let better_log = log.to_betterstack(&self.env_config, LogLevel::Debug);
-        println!("{}", better_log);
+    async fn write_sinks(&self, log: &BetterStackLogSchema) {
+        write_sinks(&self.sinks, &self.error_hook, log).await;
+    }
+}
+
+/// Writes the record to every sink accepting its level, in registration order.
+async fn write_sinks(sinks: &[SinkEntry], error_hook: &ErrorHook, log: &BetterStackLogSchema) {
+    for entry in sinks {
+        if log.level < entry.min_level {
+            continue;
+        }
+        if let Err(err) = entry.sink.write(log).await {
+            error_hook(&err);
+        }
     }
 }
 
+#[cfg(feature = "opentelemetry")]
+fn default_trace_provider() -> Option<TraceProvider> {
+    Some(Arc::new(TraceContext::current))
+}
+
+#[cfg(not(feature = "opentelemetry"))]
+fn default_trace_provider() -> Option<TraceProvider> {
+    None
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::http_client::mock::MockHttpClient;
-    use crate::r#struct::env_config::EnvConfig;
+    use crate::http_client::{CircuitBreakerConfig, Encoding};
+    use crate::sink::{ConsoleTarget, SinkFuture};
+    use crate::test_util::{RecordingSink, RecordingTransport, ScriptedResponse, SharedBuffer};
     use std::sync::atomic::Ordering;
+    use std::sync::Mutex;
+
+    fn buffered_console(buffer: &SharedBuffer) -> ConsoleSink {
+        ConsoleSink::default().with_target(ConsoleTarget::writer(buffer.clone()))
+    }
+
+    struct FailingSink;
+
+    impl Sink for FailingSink {
+        fn write<'a>(&'a self, _log: &'a BetterStackLogSchema) -> SinkFuture<'a> {
+            Box::pin(std::future::ready(Err(
+                std::io::Error::other("disk full").into()
+            )))
+        }
+    }
+
+    fn levels(sink: &RecordingSink) -> Vec<LogLevel> {
+        let records = sink.records();
+        records.into_iter().map(|record| record.level).collect()
+    }
 
     fn qa_config() -> EnvConfig {
         EnvConfig::from_values("1.0.0".to_string(), EnvEnum::QA, "token".to_string(), false)
@@ -169,4 +453,401 @@ mod tests {
 
         assert_eq!(logger.client.call_count.load(Ordering::SeqCst), 1);
     }
+
+    #[tokio::test]
+    async fn remote_enabled_sends_http_from_local_env() {
+        let mock = MockHttpClient::with_success(None);
+        let logger = Logger::with_client(local_config(), mock).with_output(OutputConfig {
+            remote: true,
+            console: false,
+        });
+
+        logger.info(test_log()).await;
+
+        assert_eq!(logger.client.call_count.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn remote_disabled_skips_http_from_non_local_env() {
+        let mock = MockHttpClient::with_success(None);
+        let logger = Logger::with_client(qa_config(), mock).with_output(OutputConfig {
+            remote: false,
+            console: false,
+        });
+
+        logger.info(test_log()).await;
+        logger.warn(test_log()).await;
+        logger.error(test_log()).await;
+
+        assert_eq!(logger.client.call_count.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn custom_sink_receives_every_level() {
+        let memory = RecordingSink::new();
+        let logger = Logger::with_client(local_config(), MockHttpClient::with_success(None))
+            .with_sink(memory.clone(), LogLevel::Debug);
+
+        logger.debug(test_log()).await;
+        logger.info(test_log()).await;
+        logger.warn(test_log()).await;
+        logger.error(test_log()).await;
+
+        assert_eq!(
+            levels(&memory),
+            vec![
+                LogLevel::Debug,
+                LogLevel::Info,
+                LogLevel::Warn,
+                LogLevel::Error
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn sink_level_filter_skips_lower_levels() {
+        let memory = RecordingSink::new();
+        let logger = Logger::with_client(local_config(), MockHttpClient::with_success(None))
+            .with_sink(memory.clone(), LogLevel::Warn);
+
+        logger.debug(test_log()).await;
+        logger.info(test_log()).await;
+        logger.warn(test_log()).await;
+        logger.error(test_log()).await;
+
+        assert_eq!(levels(&memory), vec![LogLevel::Warn, LogLevel::Error]);
+    }
+
+    #[tokio::test]
+    async fn fans_out_to_remote_and_custom_sinks() {
+        let memory = RecordingSink::new();
+        let logger = Logger::with_client(qa_config(), MockHttpClient::with_success(None))
+            .with_sink(memory.clone(), LogLevel::Info);
+
+        logger.info(test_log()).await;
+
+        assert_eq!(logger.client.call_count.load(Ordering::SeqCst), 1);
+        assert_eq!(levels(&memory), vec![LogLevel::Info]);
+    }
+
+    #[tokio::test]
+    async fn failing_sink_does_not_block_others() {
+        let memory = RecordingSink::new();
+        let logger = Logger::with_client(qa_config(), MockHttpClient::with_success(None))
+            .with_sink(FailingSink, LogLevel::Debug)
+            .with_sink(memory.clone(), LogLevel::Debug);
+
+        logger.error(test_log()).await;
+
+        assert_eq!(logger.client.call_count.load(Ordering::SeqCst), 1);
+        assert_eq!(levels(&memory), vec![LogLevel::Error]);
+    }
+
+    #[tokio::test]
+    async fn with_console_keeps_remote_sink() {
+        let logger = Logger::with_client(qa_config(), MockHttpClient::with_success(None))
+            .with_console(ConsoleSink::pretty());
+
+        logger.info(test_log()).await;
+
+        assert_eq!(logger.client.call_count.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn console_off_silences_every_level() {
+        let buffer = SharedBuffer::default();
+        let logger = Logger::with_client(local_config(), MockHttpClient::with_success(None))
+            .with_console(buffered_console(&buffer));
+
+        logger.debug(test_log()).await;
+        logger.info(test_log()).await;
+        logger.error(test_log()).await;
+
+        assert!(buffer.contents().is_empty());
+    }
+
+    #[tokio::test]
+    async fn console_on_prints_debug() {
+        let buffer = SharedBuffer::default();
+        let logger = Logger::with_client(local_config(), MockHttpClient::with_success(None))
+            .with_console(buffered_console(&buffer))
+            .with_output(OutputConfig {
+                remote: false,
+                console: true,
+            });
+
+        logger.debug(test_log()).await;
+
+        let contents = buffer.contents();
+        assert_eq!(contents.lines().count(), 1);
+        assert!(contents.contains("\"level\":\"Debug\""));
+    }
+
+    #[tokio::test]
+    async fn with_output_keeps_custom_sinks() {
+        let memory = RecordingSink::new();
+        let logger = Logger::with_client(qa_config(), MockHttpClient::with_success(None))
+            .with_sink(memory.clone(), LogLevel::Info)
+            .with_output(OutputConfig {
+                remote: false,
+                console: false,
+            });
+
+        logger.info(test_log()).await;
+
+        assert_eq!(logger.client.call_count.load(Ordering::SeqCst), 0);
+        assert_eq!(levels(&memory), vec![LogLevel::Info]);
+    }
+
+    fn recording_hook() -> (
+        impl Fn(&LogtailError) + Send + Sync + 'static,
+        Arc<Mutex<Vec<String>>>,
+    ) {
+        let reported = Arc::new(Mutex::new(Vec::new()));
+        let sink = reported.clone();
+        let hook = move |err: &LogtailError| sink.lock().unwrap().push(err.to_string());
+        (hook, reported)
+    }
+
+    #[tokio::test]
+    async fn error_hook_receives_sink_errors() {
+        let (hook, reported) = recording_hook();
+        let logger = Logger::with_client(qa_config(), MockHttpClient::with_success(None))
+            .with_output(OutputConfig {
+                remote: false,
+                console: false,
+            })
+            .with_sink(FailingSink, LogLevel::Debug)
+            .with_error_hook(hook);
+
+        logger.info(test_log()).await;
+
+        assert_eq!(*reported.lock().unwrap(), vec!["io error: disk full"]);
+    }
+
+    #[tokio::test]
+    async fn circuit_breaker_stops_requests_and_reports_opening() {
+        let (hook, reported) = recording_hook();
+        let mut config = qa_config();
+        config.delivery.circuit_breaker = Some(CircuitBreakerConfig {
+            failure_threshold: 2,
+            ..CircuitBreakerConfig::default()
+        });
+        let logger =
+            Logger::with_client(config, MockHttpClient::with_error("down")).with_error_hook(hook);
+
+        for _ in 0..4 {
+            logger.info(test_log()).await;
+        }
+
+        assert_eq!(logger.client.call_count.load(Ordering::SeqCst), 2);
+        assert_eq!(
+            *reported.lock().unwrap(),
+            vec![
+                "HTTP 500: HTTP request failed with status 500",
+                "circuit breaker open after 2 consecutive failures",
+                "HTTP 500: HTTP request failed with status 500",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn flush_delivers_spooled_records_after_recovery() {
+        let transport = RecordingTransport::new();
+        transport
+            .then_respond(ScriptedResponse::Status(503))
+            .then_respond(ScriptedResponse::Status(503));
+        let mut config = qa_config();
+        config.delivery.circuit_breaker = Some(CircuitBreakerConfig {
+            failure_threshold: 2,
+            open_for: Duration::from_millis(50),
+            spool_capacity: 10,
+        });
+        let logger = Logger::with_client(config, transport.clone()).with_error_hook(|_| {});
+        logger.info(test_log()).await;
+        logger.info(test_log()).await;
+        logger
+            .warn(LogSchema {
+                message: "spooled".to_string(),
+                context: "ctx".to_string(),
+            })
+            .await;
+
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        logger.flush().await;
+
+        assert_eq!(transport.request_count(), 3);
+        transport.assert_logged(LogLevel::Warn, "spooled");
+    }
+
+    #[tokio::test]
+    async fn reconfiguring_keeps_open_circuit() {
+        let mut config = qa_config();
+        config.delivery.circuit_breaker = Some(CircuitBreakerConfig {
+            failure_threshold: 2,
+            ..CircuitBreakerConfig::default()
+        });
+        let logger =
+            Logger::with_client(config, MockHttpClient::with_error("down")).with_error_hook(|_| {});
+        logger.info(test_log()).await;
+        logger.info(test_log()).await;
+
+        let logger = logger
+            .with_error_hook(|_| {})
+            .with_redaction(RedactionConfig::default());
+        logger.info(test_log()).await;
+
+        assert_eq!(logger.client.call_count.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn dedup_timer_writes_repeats_once_window_expires() {
+        let memory = RecordingSink::new();
+        let logger = Logger::with_client(local_config(), MockHttpClient::with_success(None))
+            .with_sink(memory.clone(), LogLevel::Debug)
+            .with_dedup(Duration::from_millis(30));
+
+        for _ in 0..3 {
+            logger.error(test_log()).await;
+        }
+        tokio::time::sleep(Duration::from_millis(80)).await;
+
+        let repeats: Vec<_> = memory
+            .records()
+            .into_iter()
+            .map(|record| record.repeat_count)
+            .collect();
+        assert_eq!(repeats, vec![None, Some(2)]);
+    }
+
+    #[tokio::test]
+    async fn dedup_collapses_repeats() {
+        let memory = RecordingSink::new();
+        let logger = Logger::with_client(local_config(), MockHttpClient::with_success(None))
+            .with_sink(memory.clone(), LogLevel::Debug)
+            .with_dedup(Duration::from_secs(60));
+
+        for _ in 0..4 {
+            logger.error(test_log()).await;
+        }
+        logger.info(test_log()).await;
+        logger.flush_repeats().await;
+
+        let repeats: Vec<_> = memory
+            .records()
+            .into_iter()
+            .map(|record| (record.level, record.repeat_count))
+            .collect();
+        assert_eq!(
+            repeats,
+            vec![
+                (LogLevel::Error, None),
+                (LogLevel::Info, None),
+                (LogLevel::Error, Some(3)),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn redacts_before_every_sink() {
+        let memory = RecordingSink::new();
+        let logger = Logger::with_client(qa_config(), MockHttpClient::with_success(None))
+            .with_sink(memory.clone(), LogLevel::Debug)
+            .with_redaction(RedactionConfig::none().with_pattern("secret-\\d+").unwrap());
+
+        logger
+            .info(LogSchema {
+                message: "using secret-42".to_string(),
+                context: "ctx".to_string(),
+            })
+            .await;
+
+        memory.assert_logged(LogLevel::Info, "using [REDACTED]");
+        let body = logger.client.captured_body.lock().unwrap().clone().unwrap();
+        assert_eq!(body["message"], "using [REDACTED]");
+    }
+
+    #[tokio::test]
+    async fn error_with_attaches_error_details() {
+        let logger = Logger::with_client(qa_config(), MockHttpClient::with_success(None));
+        let error = std::io::Error::other("disk full");
+
+        logger.error_with(&error, test_log()).await;
+
+        let body = logger.client.captured_body.lock().unwrap().clone().unwrap();
+        assert_eq!(body["level"], "Error");
+        assert_eq!(body["message"], "test");
+        assert_eq!(body["error"]["message"], "disk full");
+        assert!(body["error"].get("backtrace").is_none());
+    }
+
+    #[tokio::test]
+    async fn error_with_captures_backtrace_when_enabled() {
+        let logger = Logger::with_client(qa_config(), MockHttpClient::with_success(None))
+            .with_backtraces(true);
+        let error = std::io::Error::other("disk full");
+
+        logger.error_with(&error, test_log()).await;
+
+        let body = logger.client.captured_body.lock().unwrap().clone().unwrap();
+        assert!(body["error"]["backtrace"].is_string());
+    }
+
+    #[tokio::test]
+    async fn attaches_metadata_to_records() {
+        let logger = Logger::with_client(qa_config(), MockHttpClient::with_success(None))
+            .with_metadata(MetadataConfig::all());
+
+        logger.info(test_log()).await;
+
+        let body = logger.client.captured_body.lock().unwrap().clone().unwrap();
+        assert_eq!(body["host"]["pid"], std::process::id());
+        assert_eq!(body["host"]["os"], std::env::consts::OS);
+        assert!(body["thread"]["id"].is_string());
+    }
+
+    #[tokio::test]
+    async fn attaches_cheap_metadata_by_default() {
+        let logger = Logger::with_client(qa_config(), MockHttpClient::with_success(None));
+
+        logger.info(test_log()).await;
+
+        let body = logger.client.captured_body.lock().unwrap().clone().unwrap();
+        assert_eq!(body["host"]["pid"], std::process::id());
+        assert!(body["host"].get("os").is_none());
+        assert!(body["thread"]["id"].is_string());
+    }
+
+    #[tokio::test]
+    async fn metadata_can_be_turned_off() {
+        let logger = Logger::with_client(qa_config(), MockHttpClient::with_success(None))
+            .with_metadata(MetadataConfig::none());
+
+        logger.info(test_log()).await;
+
+        let body = logger.client.captured_body.lock().unwrap().clone().unwrap();
+        assert!(body.get("host").is_none());
+        assert!(body.get("thread").is_none());
+    }
+
+    #[tokio::test]
+    async fn attaches_trace_context() {
+        let logger = Logger::with_client(
+            qa_config(),
+            MockHttpClient::with_success(None).with_encoding(Encoding::MessagePack),
+        )
+        .with_trace_context(|| {
+            TraceContext::from_traceparent(
+                "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+            )
+        });
+
+        logger.info(test_log()).await;
+
+        let bytes = logger.client.captured_bytes();
+        let body: serde_json::Value = rmp_serde::from_slice(&bytes).unwrap();
+        assert_eq!(body["trace_id"], "4bf92f3577b34da6a3ce929d0e0e4736");
+        assert_eq!(body["span_id"], "00f067aa0ba902b7");
+        assert_eq!(body["trace_flags"], "01");
+    }
 }
@@ -0,0 +1,173 @@
+use crate::http_client::{ErrorHook, HttpClient, LogtailError};
+use crate::r#struct::betterstack_log_schema::BetterStackLogSchema;
+use crate::r#struct::env_config::EnvConfig;
+use crate::r#struct::error_details::ErrorDetails;
+use crate::r#struct::log_level::LogLevel;
+use crate::r#struct::log_schema::LogSchema;
+use crate::r#struct::metadata_config::ThreadMetadata;
+use crate::sink::{BetterStackSink, Sink};
+use crate::{write_sinks, Logger, SinkEntry};
+use std::backtrace::Backtrace;
+use std::panic::PanicHookInfo;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// How long a panicking thread waits for its report to be shipped.
+const PANIC_DELIVERY_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Name of the thread shipping a report, whose own panics are not shipped again.
+const SHIPPER_THREAD: &str = "logtail-panic";
+
+/// Ships every panic as an `Error` record through every sink of the logger, then hands
+/// over to the hook that was installed before.
+///
+/// The record holds the panic message and thread name, the panic location as its
+/// context, and a backtrace in its `error` field. Shipping blocks the panicking thread
+/// for at most 3 seconds in total, across all sinks. BetterStack shipping ignores
+/// sampling, rate limits and the circuit breaker, and goes through
+/// [`HttpClient::unpooled`] when the client offers it. Sinks added to `logger` after
+/// this call are not written to.
+pub fn install_panic_hook<C: HttpClient + 'static>(logger: &Logger<C>) {
+    let mut env_config = logger.env_config.clone();
+    env_config.delivery.sampling = None;
+    env_config.delivery.rate_limit = None;
+    env_config.delivery.circuit_breaker = None;
+    env_config.delivery.total_timeout = Some(PANIC_DELIVERY_TIMEOUT);
+    let remote = env_config.output.remote.then(|| {
+        let client = logger
+            .client
+            .unpooled()
+            .unwrap_or_else(|| logger.client.clone());
+        Arc::new(BetterStackSink::new(client, env_config.clone())) as Arc<dyn Sink>
+    });
+    let mut sinks = logger.builtin_sinks(remote);
+    sinks.extend(logger.sinks.iter().filter(|entry| !entry.builtin).cloned());
+    let sinks = Arc::new(sinks);
+    let error_hook = logger.error_hook.clone();
+    let host = logger.host.clone();
+    let trace_context = logger.trace_context.clone();
+
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let mut log = panic_record(&env_config, info);
+        log.host = host.clone();
+        if env_config.metadata.thread {
+            log.thread = Some(ThreadMetadata::current());
+        }
+        log.trace = trace_context.as_ref().and_then(|provider| provider());
+        if std::thread::current().name() != Some(SHIPPER_THREAD) {
+            ship(sinks.clone(), error_hook.clone(), log);
+        }
+        previous(info);
+    }));
+}
+
+fn panic_record(env_config: &EnvConfig, info: &PanicHookInfo<'_>) -> BetterStackLogSchema {
+    let payload = info
+        .payload()
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| info.payload().downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("Box<dyn Any>");
+    let thread = std::thread::current();
+    let log = LogSchema {
+        message: format!(
+            "thread '{}' panicked: {}",
+            thread.name().unwrap_or("<unnamed>"),
+            payload
+        ),
+        context: info.location().map(ToString::to_string).unwrap_or_default(),
+    };
+
+    let mut record = log.to_betterstack(env_config, LogLevel::Error);
+    let details = ErrorDetails {
+        message: payload.to_string(),
+        sources: Vec::new(),
+        backtrace: Some(Backtrace::force_capture().to_string()),
+    };
+    record.error = Some(details.redacted(&env_config.redaction));
+    record
+}
+
+/// Writes `log` from a fresh thread and runtime, since the panicking thread may be
+/// running, or be, an async runtime that cannot be blocked on.
+fn ship(sinks: Arc<Vec<SinkEntry>>, error_hook: ErrorHook, log: BetterStackLogSchema) {
+    let hook = error_hook.clone();
+    let shipper = std::thread::Builder::new()
+        .name(SHIPPER_THREAD.to_string())
+        .spawn(move || {
+            let runtime = match tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+            {
+                Ok(runtime) => runtime,
+                Err(err) => return hook(&err.into()),
+            };
+            let writes = write_sinks(&sinks, &hook, &log);
+            let deadline = async { tokio::time::timeout(PANIC_DELIVERY_TIMEOUT, writes).await };
+            if runtime.block_on(deadline).is_err() {
+                hook(&LogtailError::Timeout);
+            }
+        });
+    match shipper {
+        Ok(shipper) => {
+            let _ = shipper.join();
+        }
+        Err(err) => error_hook(&err.into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::r#struct::env_config::EnvEnum;
+    use crate::r#struct::metadata_config::MetadataConfig;
+    use crate::test_util::{RecordingSink, RecordingTransport};
+    use serial_test::serial;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    #[test]
+    #[serial]
+    fn ships_panics_then_calls_previous_hook() {
+        let transport = RecordingTransport::new();
+        let config =
+            EnvConfig::from_values("1.0.0".to_string(), EnvEnum::QA, "token".to_string(), false);
+        let memory = RecordingSink::new();
+        let logger = Logger::with_client(config, transport.clone())
+            .with_metadata(MetadataConfig {
+                thread: true,
+                ..MetadataConfig::none()
+            })
+            .with_sink(memory.clone(), LogLevel::Error);
+        let previous_called = Arc::new(AtomicBool::new(false));
+        let flag = previous_called.clone();
+        std::panic::set_hook(Box::new(move |_| flag.store(true, Ordering::SeqCst)));
+
+        install_panic_hook(&logger);
+        let result = std::thread::Builder::new()
+            .name("worker".to_string())
+            .spawn(|| panic!("boom {}", 42))
+            .unwrap()
+            .join();
+        drop(std::panic::take_hook());
+
+        assert!(result.is_err());
+        assert!(previous_called.load(Ordering::SeqCst));
+        transport.assert_logged(LogLevel::Error, "thread 'worker' panicked: boom 42");
+        let log = transport
+            .logs()
+            .into_iter()
+            .find(|log| log["message"] == "thread 'worker' panicked: boom 42")
+            .unwrap();
+        assert!(log["context"]
+            .as_str()
+            .unwrap()
+            .starts_with("src/panic_hook.rs:"));
+        assert_eq!(log["error"]["message"], "boom 42");
+        assert!(log["error"]["backtrace"].is_string());
+        assert_eq!(log["thread"]["name"], "worker");
+        let registered = memory.records();
+        assert_eq!(registered.len(), 1);
+        assert_eq!(registered[0].message, "thread 'worker' panicked: boom 42");
+    }
+}
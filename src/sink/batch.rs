@@ -0,0 +1,138 @@
+use crate::r#struct::betterstack_log_schema::BetterStackLogSchema;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// When a batching sink sends the records it buffered.
+///
+/// A batch is sent as soon as it holds `max_records`, or on the first write once its
+/// oldest record has waited `max_delay`; no timer sends it in between. Whatever is left
+/// is sent by `Logger::flush`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatchConfig {
+    pub max_records: usize,
+    pub max_delay: Duration,
+}
+
+impl BatchConfig {
+    /// Every record is sent on its own, as soon as it is written.
+    pub fn none() -> Self {
+        Self {
+            max_records: 1,
+            max_delay: Duration::ZERO,
+        }
+    }
+}
+
+impl Default for BatchConfig {
+    fn default() -> Self {
+        Self {
+            max_records: 100,
+            max_delay: Duration::from_secs(1),
+        }
+    }
+}
+
+pub(crate) struct Batcher {
+    config: BatchConfig,
+    state: Mutex<BatchState>,
+}
+
+#[derive(Default)]
+struct BatchState {
+    records: Vec<BetterStackLogSchema>,
+    /// When the oldest buffered record was written.
+    started: Option<Instant>,
+}
+
+impl Batcher {
+    pub(crate) fn new(config: BatchConfig) -> Self {
+        Self {
+            config,
+            state: Mutex::new(BatchState::default()),
+        }
+    }
+
+    /// Buffers `log`, returning the batch once it is due.
+    pub(crate) fn push(&self, log: &BetterStackLogSchema) -> Option<Vec<BetterStackLogSchema>> {
+        let mut state = self.state.lock().unwrap();
+        let started = *state.started.get_or_insert_with(Instant::now);
+        state.records.push(log.clone());
+        let due = state.records.len() >= self.config.max_records
+            || started.elapsed() >= self.config.max_delay;
+        due.then(|| Self::drain(&mut state))
+    }
+
+    /// Everything buffered so far, possibly nothing.
+    pub(crate) fn take(&self) -> Vec<BetterStackLogSchema> {
+        Self::drain(&mut self.state.lock().unwrap())
+    }
+
+    fn drain(state: &mut BatchState) -> Vec<BetterStackLogSchema> {
+        state.started = None;
+        std::mem::take(&mut state.records)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::r#struct::env_config::{EnvConfig, EnvEnum};
+    use crate::r#struct::log_level::LogLevel;
+
+    fn test_log(message: &str) -> BetterStackLogSchema {
+        let config =
+            EnvConfig::from_values("1.0.0".to_string(), EnvEnum::QA, "token".to_string(), false);
+        BetterStackLogSchema::new(&config, LogLevel::Info, message.to_string(), String::new())
+    }
+
+    fn messages(records: Vec<BetterStackLogSchema>) -> Vec<String> {
+        records.into_iter().map(|record| record.message).collect()
+    }
+
+    #[test]
+    fn releases_full_batches() {
+        let batcher = Batcher::new(BatchConfig {
+            max_records: 2,
+            max_delay: Duration::from_secs(60),
+        });
+
+        assert!(batcher.push(&test_log("one")).is_none());
+        let batch = batcher.push(&test_log("two")).unwrap();
+
+        assert_eq!(messages(batch), vec!["one", "two"]);
+        assert!(batcher.take().is_empty());
+    }
+
+    #[test]
+    fn releases_stale_batches() {
+        let batcher = Batcher::new(BatchConfig {
+            max_records: 100,
+            max_delay: Duration::from_millis(20),
+        });
+
+        assert!(batcher.push(&test_log("one")).is_none());
+        std::thread::sleep(Duration::from_millis(30));
+        let batch = batcher.push(&test_log("two")).unwrap();
+
+        assert_eq!(messages(batch), vec!["one", "two"]);
+    }
+
+    #[test]
+    fn none_releases_every_record() {
+        let batcher = Batcher::new(BatchConfig::none());
+
+        assert_eq!(
+            messages(batcher.push(&test_log("one")).unwrap()),
+            vec!["one"]
+        );
+    }
+
+    #[test]
+    fn take_drains_partial_batch() {
+        let batcher = Batcher::new(BatchConfig::default());
+        batcher.push(&test_log("one"));
+
+        assert_eq!(messages(batcher.take()), vec!["one"]);
+        assert!(batcher.take().is_empty());
+    }
+}
@@ -0,0 +1,515 @@
+use super::throttle::{Summary, Throttle};
+use super::{Sink, SinkFuture};
+use crate::http_client::{
+    service, CircuitBreaker, ErrorHook, HttpClient, LogtailError, ReqwestClient,
+};
+use crate::r#struct::betterstack_log_schema::BetterStackLogSchema;
+use crate::r#struct::env_config::EnvConfig;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+/// Ships records to BetterStack through an [`HttpClient`].
+///
+/// Records are first sampled and rate-limited per `env_config.delivery`. Behind its circuit
+/// breaker, records written while the circuit is open are spooled, oldest dropped first,
+/// and sent in one batch once a request succeeds.
+pub struct BetterStackSink<C: HttpClient = ReqwestClient> {
+    client: C,
+    env_config: EnvConfig,
+    throttle: Option<Throttle>,
+    breaker: Option<CircuitBreaker>,
+    spool: Mutex<VecDeque<BetterStackLogSchema>>,
+}
+
+impl<C: HttpClient> BetterStackSink<C> {
+    pub fn new(client: C, env_config: EnvConfig) -> Self {
+        let breaker = env_config
+            .delivery
+            .circuit_breaker
+            .clone()
+            .map(CircuitBreaker::new);
+        Self {
+            client,
+            throttle: Throttle::from_config(&env_config.delivery),
+            env_config,
+            breaker,
+            spool: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    /// Reports circuit breaker transitions to `hook`.
+    pub fn with_error_hook(mut self, hook: ErrorHook) -> Self {
+        self.breaker = self.breaker.map(|breaker| breaker.with_hook(hook));
+        self
+    }
+
+    /// Carries the rate limits, circuit state and spooled records of `previous` over,
+    /// so reconfiguring the logger does not lose them.
+    pub(crate) fn with_state_of(mut self, previous: Self) -> Self {
+        if previous.env_config.delivery == self.env_config.delivery {
+            self.throttle = previous.throttle;
+        }
+        if let (Some(breaker), Some(previous)) = (&self.breaker, previous.breaker) {
+            breaker.restore(previous);
+        }
+        self.spool = previous.spool;
+        self
+    }
+
+    async fn ship(&self, log: &BetterStackLogSchema) -> Result<(), LogtailError> {
+        match &self.breaker {
+            Some(breaker) => self.write_guarded(breaker, log).await,
+            None => {
+                service::send_log(&self.client, &self.env_config, log).await?;
+                Ok(())
+            }
+        }
+    }
+
+    /// Ships the summary of dropped records, resetting its counts only once it went out.
+    async fn ship_summary(&self, summary: Summary<'_>) -> Result<(), LogtailError> {
+        self.ship(&summary.record).await?;
+        summary.sent();
+        Ok(())
+    }
+
+    async fn write_guarded(
+        &self,
+        breaker: &CircuitBreaker,
+        log: &BetterStackLogSchema,
+    ) -> Result<(), LogtailError> {
+        if !breaker.allow() {
+            self.spool(breaker, vec![log.clone()]);
+            return Ok(());
+        }
+        if let Err(err) = service::send_log(&self.client, &self.env_config, log).await {
+            record(breaker, &err);
+            return Err(err);
+        }
+        breaker.record_success();
+        self.flush_spool(breaker).await
+    }
+
+    async fn flush_spool(&self, breaker: &CircuitBreaker) -> Result<(), LogtailError> {
+        let spooled: Vec<_> = self.spool.lock().unwrap().drain(..).collect();
+        if spooled.is_empty() {
+            return Ok(());
+        }
+        let report = service::send_batches(&self.client, &self.env_config, &spooled).await;
+        // only what could not be delivered for now goes back, never what was delivered or rejected
+        if report.retryable.is_empty() {
+            breaker.record_success();
+        } else {
+            breaker.record_failure();
+            self.requeue(breaker, report.retryable);
+        }
+        match report.error {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+
+    /// Sends the spool when the circuit lets a request through, so nothing spooled is left
+    /// behind at shutdown.
+    async fn drain_spool(&self) -> Result<(), LogtailError> {
+        let Some(breaker) = &self.breaker else {
+            return Ok(());
+        };
+        if self.spool.lock().unwrap().is_empty() || !breaker.allow() {
+            return Ok(());
+        }
+        self.flush_spool(breaker).await
+    }
+
+    /// Queues `logs` behind the records already spooled, dropping the oldest beyond
+    /// `spool_capacity`.
+    fn spool(&self, breaker: &CircuitBreaker, logs: Vec<BetterStackLogSchema>) {
+        let mut spool = self.spool.lock().unwrap();
+        spool.extend(logs);
+        truncate_front(&mut spool, breaker.config().spool_capacity);
+    }
+
+    /// Puts `logs` taken from the spool back ahead of the records spooled since.
+    fn requeue(&self, breaker: &CircuitBreaker, logs: Vec<BetterStackLogSchema>) {
+        let mut spool = self.spool.lock().unwrap();
+        for log in logs.into_iter().rev() {
+            spool.push_front(log);
+        }
+        truncate_front(&mut spool, breaker.config().spool_capacity);
+    }
+}
+
+/// Drops the oldest records until at most `capacity` are left.
+fn truncate_front(spool: &mut VecDeque<BetterStackLogSchema>, capacity: usize) {
+    let excess = spool.len().saturating_sub(capacity);
+    spool.drain(..excess);
+}
+
+/// Only failures to reach the endpoint count against the circuit, a rejected record does not.
+fn record(breaker: &CircuitBreaker, err: &LogtailError) {
+    if err.is_retryable() {
+        breaker.record_failure();
+    } else {
+        breaker.record_success();
+    }
+}
+
+impl<C: HttpClient> Sink for BetterStackSink<C> {
+    fn write<'a>(&'a self, log: &'a BetterStackLogSchema) -> SinkFuture<'a> {
+        Box::pin(async move {
+            let Some(throttle) = &self.throttle else {
+                return self.ship(log).await;
+            };
+            let summary = match throttle.take_summary(&self.env_config, false) {
+                Some(summary) => self.ship_summary(summary).await,
+                None => Ok(()),
+            };
+            if throttle.admit(&log.level) {
+                self.ship(log).await?;
+            }
+            summary
+        })
+    }
+
+    /// Sends the spooled records when the circuit allows it, then the summary of dropped
+    /// records without waiting for `summary_interval`.
+    fn flush(&self) -> SinkFuture<'_> {
+        Box::pin(async move {
+            let spool = self.drain_spool().await;
+            let summary = match self
+                .throttle
+                .as_ref()
+                .and_then(|throttle| throttle.take_summary(&self.env_config, true))
+            {
+                Some(summary) => self.ship_summary(summary).await,
+                None => Ok(()),
+            };
+            spool.and(summary)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http_client::mock::MockHttpClient;
+    use crate::http_client::{CircuitBreakerConfig, CircuitState};
+    use crate::r#struct::env_config::EnvEnum;
+    use crate::r#struct::log_level::LogLevel;
+    use crate::sink::{RateLimitConfig, SamplingConfig};
+    use crate::test_util::{RecordingTransport, ScriptedResponse};
+    use std::time::Duration;
+
+    fn test_config() -> EnvConfig {
+        EnvConfig::from_values("1.0.0".to_string(), EnvEnum::QA, "token".to_string(), false)
+    }
+
+    fn breaker_config(open_for: Duration, spool_capacity: usize) -> EnvConfig {
+        let mut config = test_config();
+        config.delivery.circuit_breaker = Some(CircuitBreakerConfig {
+            failure_threshold: 2,
+            open_for,
+            spool_capacity,
+        });
+        config
+    }
+
+    fn message_log(message: &str) -> BetterStackLogSchema {
+        BetterStackLogSchema {
+            message: message.to_string(),
+            ..test_log()
+        }
+    }
+
+    fn test_log() -> BetterStackLogSchema {
+        BetterStackLogSchema::fixture(LogLevel::Warn, "test message")
+    }
+
+    #[tokio::test]
+    async fn write_posts_log() {
+        let sink = BetterStackSink::new(MockHttpClient::with_success(None), test_config());
+
+        sink.write(&test_log()).await.unwrap();
+
+        let body = sink.client.captured_body.lock().unwrap().clone().unwrap();
+        assert_eq!(body["message"], "test message");
+        assert_eq!(body["level"], "Warn");
+        assert_eq!(body["dt"], "1970-01-01T00:00:00.000Z");
+    }
+
+    #[tokio::test]
+    async fn write_returns_http_error() {
+        let sink = BetterStackSink::new(MockHttpClient::with_error("boom"), test_config());
+
+        let result = sink.write(&test_log()).await;
+
+        assert!(matches!(result, Err(LogtailError::Http { .. })));
+    }
+
+    #[tokio::test]
+    async fn open_circuit_short_circuits_writes() {
+        let transport = RecordingTransport::new();
+        transport.always_respond(ScriptedResponse::Status(503));
+        let sink = BetterStackSink::new(
+            transport.clone(),
+            breaker_config(Duration::from_secs(60), 0),
+        );
+
+        assert!(sink.write(&test_log()).await.is_err());
+        assert!(sink.write(&test_log()).await.is_err());
+        assert!(sink.write(&test_log()).await.is_ok());
+
+        assert_eq!(transport.request_count(), 2);
+        let state = sink.breaker.as_ref().unwrap().state();
+        assert_eq!(state, CircuitState::Open { failures: 2 });
+    }
+
+    #[tokio::test]
+    async fn client_errors_do_not_open_circuit() {
+        let transport = RecordingTransport::new();
+        transport.always_respond(ScriptedResponse::Status(400));
+        let sink = BetterStackSink::new(
+            transport.clone(),
+            breaker_config(Duration::from_secs(60), 0),
+        );
+
+        for _ in 0..3 {
+            assert!(sink.write(&test_log()).await.is_err());
+        }
+
+        assert_eq!(transport.request_count(), 3);
+    }
+
+    #[tokio::test]
+    async fn spooled_records_are_sent_after_recovery() {
+        let transport = RecordingTransport::new();
+        transport
+            .then_respond(ScriptedResponse::Status(503))
+            .then_respond(ScriptedResponse::Status(503));
+        let sink = BetterStackSink::new(
+            transport.clone(),
+            breaker_config(Duration::from_millis(50), 10),
+        );
+        sink.write(&message_log("failed 1")).await.ok();
+        sink.write(&message_log("failed 2")).await.ok();
+
+        sink.write(&message_log("spooled")).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        sink.write(&message_log("probe")).await.unwrap();
+
+        let requests = transport.requests();
+        assert_eq!(requests.len(), 4);
+        transport.assert_logged(LogLevel::Warn, "probe");
+        transport.assert_logged(LogLevel::Warn, "spooled");
+        let state = sink.breaker.as_ref().unwrap().state();
+        assert_eq!(state, CircuitState::Closed);
+    }
+
+    #[tokio::test]
+    async fn flush_sends_spool_once_circuit_allows() {
+        let transport = RecordingTransport::new();
+        transport
+            .then_respond(ScriptedResponse::Status(503))
+            .then_respond(ScriptedResponse::Status(503));
+        let sink = BetterStackSink::new(
+            transport.clone(),
+            breaker_config(Duration::from_millis(50), 10),
+        );
+        sink.write(&message_log("failed 1")).await.ok();
+        sink.write(&message_log("failed 2")).await.ok();
+        sink.write(&message_log("spooled")).await.unwrap();
+
+        sink.flush().await.unwrap();
+        assert_eq!(transport.request_count(), 2);
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        sink.flush().await.unwrap();
+
+        assert_eq!(transport.request_count(), 3);
+        transport.assert_logged(LogLevel::Warn, "spooled");
+        assert!(sink.spool.lock().unwrap().is_empty());
+        let state = sink.breaker.as_ref().unwrap().state();
+        assert_eq!(state, CircuitState::Closed);
+    }
+
+    #[tokio::test]
+    async fn spool_drops_oldest_records() {
+        let transport = RecordingTransport::new();
+        transport.always_respond(ScriptedResponse::Status(503));
+        let sink = BetterStackSink::new(
+            transport.clone(),
+            breaker_config(Duration::from_secs(60), 2),
+        );
+        sink.write(&test_log()).await.ok();
+        sink.write(&test_log()).await.ok();
+
+        for message in ["first", "second", "third"] {
+            sink.write(&message_log(message)).await.unwrap();
+        }
+
+        let spool = sink.spool.lock().unwrap();
+        let messages: Vec<_> = spool.iter().map(|log| log.message.as_str()).collect();
+        assert_eq!(messages, vec!["second", "third"]);
+    }
+
+    #[test]
+    fn failed_spool_flush_keeps_oldest_records_first() {
+        let sink = BetterStackSink::new(
+            RecordingTransport::new(),
+            breaker_config(Duration::from_secs(60), 10),
+        );
+        let breaker = sink.breaker.as_ref().unwrap();
+        sink.spool(breaker, vec![message_log("old")]);
+
+        let spooled: Vec<_> = sink.spool.lock().unwrap().drain(..).collect();
+        sink.spool(breaker, vec![message_log("new")]);
+        sink.requeue(breaker, spooled);
+
+        let spool = sink.spool.lock().unwrap();
+        let messages: Vec<_> = spool.iter().map(|log| log.message.as_str()).collect();
+        assert_eq!(messages, vec!["old", "new"]);
+    }
+
+    #[tokio::test]
+    async fn rejected_spool_flush_is_not_spooled_again() {
+        let transport = RecordingTransport::new();
+        let sink = BetterStackSink::new(
+            transport.clone(),
+            breaker_config(Duration::from_secs(60), 10),
+        );
+        let breaker = sink.breaker.as_ref().unwrap();
+        sink.spool(breaker, vec![message_log("spooled")]);
+        transport
+            .then_respond(ScriptedResponse::Success)
+            .then_respond(ScriptedResponse::Status(400));
+
+        let result = sink.write(&message_log("fresh")).await;
+
+        assert!(matches!(
+            result,
+            Err(LogtailError::Http { status: 400, .. })
+        ));
+        assert!(sink.spool.lock().unwrap().is_empty());
+        assert_eq!(breaker.state(), CircuitState::Closed);
+    }
+
+    #[tokio::test]
+    async fn cancelled_probe_reopens_circuit() {
+        let transport = RecordingTransport::new();
+        transport
+            .then_respond(ScriptedResponse::Status(503))
+            .then_respond(ScriptedResponse::Status(503))
+            .then_respond(ScriptedResponse::Success.delayed(Duration::from_secs(60)));
+        let sink = BetterStackSink::new(
+            transport.clone(),
+            breaker_config(Duration::from_millis(50), 10),
+        );
+        sink.write(&test_log()).await.ok();
+        sink.write(&test_log()).await.ok();
+        tokio::time::sleep(Duration::from_millis(60)).await;
+
+        let probe = tokio::time::timeout(Duration::from_millis(10), sink.write(&test_log())).await;
+        assert!(probe.is_err());
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        sink.write(&message_log("after cancel")).await.unwrap();
+
+        let breaker = sink.breaker.as_ref().unwrap();
+        assert_eq!(breaker.state(), CircuitState::Open { failures: 2 });
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        sink.write(&message_log("second probe")).await.unwrap();
+        assert_eq!(breaker.state(), CircuitState::Closed);
+        transport.assert_logged(LogLevel::Warn, "after cancel");
+    }
+
+    #[tokio::test]
+    async fn with_state_of_keeps_circuit_and_spool() {
+        let transport = RecordingTransport::new();
+        transport.always_respond(ScriptedResponse::Status(503));
+        let config = breaker_config(Duration::from_secs(60), 10);
+        let previous = BetterStackSink::new(transport.clone(), config.clone());
+        previous.write(&test_log()).await.ok();
+        previous.write(&test_log()).await.ok();
+        previous.write(&message_log("spooled")).await.unwrap();
+
+        let sink = BetterStackSink::new(transport.clone(), config).with_state_of(previous);
+
+        let state = sink.breaker.as_ref().unwrap().state();
+        assert_eq!(state, CircuitState::Open { failures: 2 });
+        assert_eq!(sink.spool.lock().unwrap()[0].message, "spooled");
+    }
+
+    #[tokio::test]
+    async fn rate_limited_records_are_not_sent() {
+        let transport = RecordingTransport::new();
+        let mut config = test_config();
+        config.delivery.rate_limit = Some(RateLimitConfig {
+            per_second: 0,
+            burst: 2,
+        });
+        let sink = BetterStackSink::new(transport.clone(), config);
+
+        for _ in 0..5 {
+            sink.write(&test_log()).await.unwrap();
+        }
+
+        assert_eq!(transport.request_count(), 2);
+    }
+
+    #[tokio::test]
+    async fn ships_summary_of_suppressed_records() {
+        let transport = RecordingTransport::new();
+        let mut config = test_config();
+        config.delivery.sampling = Some(SamplingConfig {
+            warn: 0.0,
+            ..SamplingConfig::default()
+        });
+        config.delivery.summary_interval = Duration::ZERO;
+        let sink = BetterStackSink::new(transport.clone(), config);
+
+        sink.write(&test_log()).await.unwrap();
+        sink.write(&test_log()).await.unwrap();
+
+        assert_eq!(transport.request_count(), 1);
+        transport.assert_logged(LogLevel::Warn, "suppressed 1 logs");
+    }
+
+    #[tokio::test]
+    async fn flush_ships_summary_before_interval() {
+        let transport = RecordingTransport::new();
+        let mut config = test_config();
+        config.delivery.sampling = Some(SamplingConfig {
+            warn: 0.0,
+            ..SamplingConfig::default()
+        });
+        let sink = BetterStackSink::new(transport.clone(), config);
+        sink.write(&test_log()).await.unwrap();
+
+        sink.flush().await.unwrap();
+
+        transport.assert_logged(LogLevel::Warn, "suppressed 1 logs");
+        sink.flush().await.unwrap();
+        assert_eq!(transport.request_count(), 1);
+    }
+
+    #[tokio::test]
+    async fn failed_summary_is_retried() {
+        let transport = RecordingTransport::new();
+        transport.then_respond(ScriptedResponse::Status(400));
+        let mut config = test_config();
+        config.delivery.sampling = Some(SamplingConfig {
+            warn: 0.0,
+            ..SamplingConfig::default()
+        });
+        let sink = BetterStackSink::new(transport.clone(), config);
+        sink.write(&test_log()).await.unwrap();
+
+        assert!(sink.flush().await.is_err());
+        sink.flush().await.unwrap();
+
+        assert_eq!(transport.request_count(), 2);
+        let last = transport.logs().pop().unwrap();
+        assert!(last["message"]
+            .as_str()
+            .unwrap()
+            .starts_with("suppressed 1 logs"));
+    }
+}
@@ -0,0 +1,194 @@
+use super::format::{auto_formatter, ConsoleFormatter, JsonFormatter, PrettyFormatter};
+use super::{Sink, SinkFuture};
+use crate::http_client::LogtailError;
+use crate::r#struct::betterstack_log_schema::BetterStackLogSchema;
+use crate::r#struct::log_level::LogLevel;
+use std::future;
+use std::io::{self, Write};
+use std::sync::{Arc, Mutex, PoisonError};
+
+/// Where `ConsoleSink` writes its lines.
+#[derive(Clone)]
+pub enum ConsoleTarget {
+    Stdout,
+    /// Keeps logs out of the application's own stdout.
+    Stderr,
+    /// Levels at or above `stderr_from` go to stderr, the rest to stdout.
+    Split {
+        stderr_from: LogLevel,
+    },
+    /// Any shared writer, e.g. a buffer in tests or a pipe to another process.
+    Writer(Arc<Mutex<dyn Write + Send>>),
+}
+
+impl ConsoleTarget {
+    pub fn writer(writer: impl Write + Send + 'static) -> Self {
+        Self::Writer(Arc::new(Mutex::new(writer)))
+    }
+}
+
+impl Default for ConsoleTarget {
+    /// Historical routing: errors to stderr, everything else to stdout.
+    fn default() -> Self {
+        Self::Split {
+            stderr_from: LogLevel::Error,
+        }
+    }
+}
+
+/// Prints one formatted line per record to a [`ConsoleTarget`].
+///
+/// Uses [`JsonFormatter`] unless built with another [`ConsoleFormatter`]. Each line is
+/// written under the stream's lock, so concurrent records never interleave mid-line.
+#[derive(Clone)]
+pub struct ConsoleSink {
+    formatter: Arc<dyn ConsoleFormatter>,
+    target: ConsoleTarget,
+}
+
+impl Default for ConsoleSink {
+    fn default() -> Self {
+        Self::new(JsonFormatter)
+    }
+}
+
+impl ConsoleSink {
+    pub fn new(formatter: impl ConsoleFormatter + 'static) -> Self {
+        Self {
+            formatter: Arc::new(formatter),
+            target: ConsoleTarget::default(),
+        }
+    }
+
+    /// Pretty lines, colored when attached to a terminal.
+    pub fn pretty() -> Self {
+        Self::new(PrettyFormatter::default())
+    }
+
+    /// Pretty lines on a terminal, JSON when the output is piped.
+    pub fn auto() -> Self {
+        Self {
+            formatter: Arc::from(auto_formatter()),
+            target: ConsoleTarget::default(),
+        }
+    }
+
+    pub fn with_target(mut self, target: ConsoleTarget) -> Self {
+        self.target = target;
+        self
+    }
+
+    fn write_line(&self, log: &BetterStackLogSchema) -> Result<(), LogtailError> {
+        let mut line = self.formatter.format(log);
+        line.push('\n');
+
+        match &self.target {
+            ConsoleTarget::Stdout => write_locked(&mut io::stdout().lock(), &line),
+            ConsoleTarget::Stderr => write_locked(&mut io::stderr().lock(), &line),
+            ConsoleTarget::Split { stderr_from } if log.level >= *stderr_from => {
+                write_locked(&mut io::stderr().lock(), &line)
+            }
+            ConsoleTarget::Split { .. } => write_locked(&mut io::stdout().lock(), &line),
+            // a writer that panicked once must not make every later log call panic
+            ConsoleTarget::Writer(writer) => write_locked(
+                &mut *writer.lock().unwrap_or_else(PoisonError::into_inner),
+                &line,
+            ),
+        }
+    }
+}
+
+impl Sink for ConsoleSink {
+    fn write<'a>(&'a self, log: &'a BetterStackLogSchema) -> SinkFuture<'a> {
+        Box::pin(future::ready(self.write_line(log)))
+    }
+}
+
+fn write_locked(writer: &mut (impl Write + ?Sized), line: &str) -> Result<(), LogtailError> {
+    writer.write_all(line.as_bytes())?;
+    writer.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::SharedBuffer;
+    use serial_test::serial;
+
+    fn test_log(message: &str) -> BetterStackLogSchema {
+        BetterStackLogSchema::fixture(LogLevel::Info, message)
+    }
+
+    #[tokio::test]
+    async fn writer_target_receives_formatted_lines() {
+        let buffer = SharedBuffer::default();
+        let sink = ConsoleSink::default().with_target(ConsoleTarget::writer(buffer.clone()));
+
+        sink.write(&test_log("first")).await.unwrap();
+        sink.write(&test_log("second")).await.unwrap();
+
+        assert_eq!(
+            buffer.contents(),
+            format!("{}\n{}\n", test_log("first"), test_log("second"))
+        );
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn writes_after_writer_lock_is_poisoned() {
+        let buffer = SharedBuffer::default();
+        let target = ConsoleTarget::writer(buffer.clone());
+        let ConsoleTarget::Writer(writer) = &target else {
+            unreachable!();
+        };
+        let poison = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            let _writer = writer.lock().unwrap();
+            panic!("poison");
+        }));
+        assert!(poison.is_err());
+        let sink = ConsoleSink::default().with_target(target);
+
+        sink.write(&test_log("after")).await.unwrap();
+
+        assert_eq!(buffer.contents(), format!("{}\n", test_log("after")));
+    }
+
+    #[tokio::test]
+    async fn concurrent_writes_keep_lines_whole() {
+        let buffer = SharedBuffer::default();
+        let sink = ConsoleSink::default().with_target(ConsoleTarget::writer(buffer.clone()));
+        let sink = Arc::new(sink);
+
+        let handles: Vec<_> = (0..8)
+            .map(|i| {
+                let sink = sink.clone();
+                tokio::spawn(async move {
+                    for _ in 0..50 {
+                        sink.write(&test_log(&format!("task {}", i))).await.unwrap();
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.await.unwrap();
+        }
+
+        let contents = buffer.contents();
+        assert_eq!(contents.lines().count(), 400);
+        for line in contents.lines() {
+            let parsed: serde_json::Value = serde_json::from_str(line).unwrap();
+            assert!(parsed["message"].as_str().unwrap().starts_with("task "));
+        }
+    }
+
+    #[test]
+    fn default_target_splits_errors_to_stderr() {
+        assert!(matches!(
+            ConsoleTarget::default(),
+            ConsoleTarget::Split {
+                stderr_from: LogLevel::Error
+            }
+        ));
+    }
+}
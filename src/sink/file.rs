@@ -0,0 +1,461 @@
+use super::{Sink, SinkFuture};
+use crate::http_client::LogtailError;
+use crate::r#struct::betterstack_log_schema::BetterStackLogSchema;
+use crate::time::{days_since_epoch, format_date};
+#[cfg(feature = "compression")]
+use flate2::write::GzEncoder;
+use std::fs::{self, File, OpenOptions};
+use std::future;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, PoisonError};
+use std::time::SystemTime;
+
+/// When the active log file is closed and a new one started.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Rotation {
+    /// Keep appending to the same file.
+    Never,
+    /// Rotate before a line would grow the file past this many bytes.
+    Size(u64),
+    /// Rotate on the first write of each UTC day.
+    Daily,
+}
+
+/// Appends records to a local file as JSON lines, one `Display` of the record per line.
+///
+/// Rotated segments are renamed to `<file>.<YYYY-MM-DD>.<n>` next to the active file,
+/// gzipped with the `compression` feature, and pruned down to the configured retention.
+pub struct FileSink {
+    path: PathBuf,
+    rotation: Rotation,
+    retention: Option<usize>,
+    #[cfg(feature = "compression")]
+    compress: bool,
+    state: Mutex<FileState>,
+}
+
+struct FileState {
+    file: File,
+    size: u64,
+    /// Day, since the Unix epoch, the active file was started.
+    day: u64,
+}
+
+impl FileSink {
+    /// Opens `path` for appending, creating it if needed. Rotation is off by default.
+    pub fn new(path: impl AsRef<Path>) -> Result<Self, LogtailError> {
+        let path = path.as_ref().to_path_buf();
+        let state = open_state(&path, SystemTime::now())?;
+        Ok(Self {
+            path,
+            rotation: Rotation::Never,
+            retention: None,
+            #[cfg(feature = "compression")]
+            compress: false,
+            state: Mutex::new(state),
+        })
+    }
+
+    pub fn with_rotation(mut self, rotation: Rotation) -> Self {
+        self.rotation = rotation;
+        self
+    }
+
+    /// Keeps at most `files` rotated segments, deleting the oldest ones.
+    pub fn with_retention(mut self, files: usize) -> Self {
+        self.retention = Some(files);
+        self
+    }
+
+    /// Gzips rotated segments into `<segment>.gz`.
+    #[cfg(feature = "compression")]
+    pub fn with_compression(mut self, compress: bool) -> Self {
+        self.compress = compress;
+        self
+    }
+
+    fn write_line(&self, log: &BetterStackLogSchema, now: SystemTime) -> Result<(), LogtailError> {
+        let line = format!("{}\n", log);
+        // a panic elsewhere while holding the lock must not fail every later write
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+
+        if self.should_rotate(&state, line.len() as u64, now) {
+            self.rotate(&mut state, now)?;
+        }
+
+        state.file.write_all(line.as_bytes())?;
+        state.size += line.len() as u64;
+        Ok(())
+    }
+
+    fn should_rotate(&self, state: &FileState, incoming: u64, now: SystemTime) -> bool {
+        match self.rotation {
+            Rotation::Never => false,
+            Rotation::Size(max) => state.size > 0 && state.size + incoming > max,
+            Rotation::Daily => days_since_epoch(now) != state.day,
+        }
+    }
+
+    fn rotate(&self, state: &mut FileState, now: SystemTime) -> Result<(), LogtailError> {
+        state.file.flush()?;
+        let segment = self.next_segment_path(state.day)?;
+        fs::rename(&self.path, &segment)?;
+        *state = open_state(&self.path, now)?;
+
+        #[cfg(feature = "compression")]
+        if self.compress {
+            compress_segment(&segment)?;
+        }
+        if let Some(retention) = self.retention {
+            self.prune(retention)?;
+        }
+        Ok(())
+    }
+
+    fn file_name(&self) -> String {
+        self.path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    }
+
+    fn directory(&self) -> PathBuf {
+        match self.path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+            _ => PathBuf::from("."),
+        }
+    }
+
+    /// Next `<file>.<date>.<n>` name, numbered after every existing segment of that date.
+    fn next_segment_path(&self, day: u64) -> io::Result<PathBuf> {
+        let date = format_date(day);
+        let last = self
+            .segments()?
+            .into_iter()
+            .filter(|((segment_date, _), _)| *segment_date == date)
+            .map(|((_, n), _)| n)
+            .max()
+            .unwrap_or(0);
+        let name = format!("{}.{}.{}", self.file_name(), date, last + 1);
+        Ok(self.directory().join(name))
+    }
+
+    /// Rotated segments of this file with their `(date, n)` key, oldest first.
+    fn segments(&self) -> io::Result<Vec<((String, u64), PathBuf)>> {
+        let prefix = format!("{}.", self.file_name());
+        let mut segments = Vec::new();
+
+        for entry in fs::read_dir(self.directory())? {
+            let path = entry?.path();
+            let name = path.file_name().unwrap().to_string_lossy().into_owned();
+            if let Some(key) = name
+                .strip_prefix(&prefix)
+                .and_then(|suffix| segment_key(suffix.trim_end_matches(".gz")))
+            {
+                segments.push((key, path));
+            }
+        }
+
+        segments.sort();
+        Ok(segments)
+    }
+
+    fn prune(&self, retention: usize) -> io::Result<()> {
+        let segments = self.segments()?;
+        let excess = segments.len().saturating_sub(retention);
+        for (_, segment) in &segments[..excess] {
+            fs::remove_file(segment)?;
+        }
+        Ok(())
+    }
+}
+
+impl Sink for FileSink {
+    fn write<'a>(&'a self, log: &'a BetterStackLogSchema) -> SinkFuture<'a> {
+        Box::pin(future::ready(self.write_line(log, SystemTime::now())))
+    }
+}
+
+fn open_state(path: &Path, now: SystemTime) -> io::Result<FileState> {
+    let file = OpenOptions::new().create(true).append(true).open(path)?;
+    let metadata = file.metadata()?;
+    // an existing file belongs to the day it was last written, so a restart on the
+    // next day still rotates it under the right date
+    let day = if metadata.len() > 0 {
+        days_since_epoch(metadata.modified()?)
+    } else {
+        days_since_epoch(now)
+    };
+    Ok(FileState {
+        file,
+        size: metadata.len(),
+        day,
+    })
+}
+
+/// Sort key of a `<YYYY-MM-DD>.<n>` segment suffix, `None` for unrelated files.
+fn segment_key(suffix: &str) -> Option<(String, u64)> {
+    let (date, n) = suffix.rsplit_once('.')?;
+    let is_date = date.len() == 10
+        && date.chars().enumerate().all(|(i, c)| {
+            if i == 4 || i == 7 {
+                c == '-'
+            } else {
+                c.is_ascii_digit()
+            }
+        });
+    if !is_date {
+        return None;
+    }
+    Some((date.to_string(), n.parse().ok()?))
+}
+
+#[cfg(feature = "compression")]
+fn compress_segment(path: &Path) -> io::Result<()> {
+    let mut gz_name = path.as_os_str().to_os_string();
+    gz_name.push(".gz");
+    let mut input = File::open(path)?;
+    let mut encoder = GzEncoder::new(File::create(gz_name)?, flate2::Compression::default());
+    io::copy(&mut input, &mut encoder)?;
+    encoder.finish()?;
+    fs::remove_file(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::r#struct::log_level::LogLevel;
+    use serial_test::serial;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    const DAY: u64 = 86_400;
+
+    fn test_log(message: &str) -> BetterStackLogSchema {
+        BetterStackLogSchema::fixture(LogLevel::Info, message)
+    }
+
+    fn line_len() -> u64 {
+        test_log("x").to_string().len() as u64 + 1
+    }
+
+    fn file_names(dir: &Path) -> Vec<String> {
+        let mut names: Vec<String> = fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[tokio::test]
+    async fn appends_one_json_line_per_record() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.log");
+        let sink = FileSink::new(&path).unwrap();
+
+        sink.write(&test_log("first")).await.unwrap();
+        sink.write(&test_log("second")).await.unwrap();
+
+        let contents = fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = contents.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0], test_log("first").to_string());
+        assert_eq!(lines[1], test_log("second").to_string());
+    }
+
+    #[tokio::test]
+    async fn keeps_existing_contents() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.log");
+        fs::write(&path, "previous\n").unwrap();
+
+        let sink = FileSink::new(&path).unwrap();
+        sink.write(&test_log("next")).await.unwrap();
+
+        let contents = fs::read_to_string(&path).unwrap();
+        assert!(contents.starts_with("previous\n"));
+        assert_eq!(contents.lines().count(), 2);
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn writes_after_lock_is_poisoned() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.log");
+        let sink = FileSink::new(&path).unwrap();
+        let poison = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            let _state = sink.state.lock().unwrap();
+            panic!("poison");
+        }));
+        assert!(poison.is_err());
+
+        sink.write(&test_log("after")).await.unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap().lines().count(), 1);
+    }
+
+    #[test]
+    fn new_fails_for_missing_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let result = FileSink::new(dir.path().join("missing").join("app.log"));
+        assert!(matches!(result, Err(LogtailError::Io(_))));
+    }
+
+    #[test]
+    fn size_rotation_starts_new_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.log");
+        let sink = FileSink::new(&path)
+            .unwrap()
+            .with_rotation(Rotation::Size(line_len() * 2));
+        let now = SystemTime::now();
+        let today = format_date(days_since_epoch(now));
+
+        for _ in 0..5 {
+            sink.write_line(&test_log("x"), now).unwrap();
+        }
+
+        assert_eq!(
+            file_names(dir.path()),
+            vec![
+                "app.log".to_string(),
+                format!("app.log.{}.1", today),
+                format!("app.log.{}.2", today),
+            ]
+        );
+        assert_eq!(fs::read_to_string(&path).unwrap().lines().count(), 1);
+        let segment = dir.path().join(format!("app.log.{}.1", today));
+        assert_eq!(fs::read_to_string(segment).unwrap().lines().count(), 2);
+    }
+
+    #[test]
+    fn size_rotation_never_leaves_empty_segment() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.log");
+        let sink = FileSink::new(&path)
+            .unwrap()
+            .with_rotation(Rotation::Size(1));
+
+        sink.write_line(&test_log("x"), SystemTime::now()).unwrap();
+
+        assert_eq!(file_names(dir.path()), vec!["app.log".to_string()]);
+    }
+
+    #[test]
+    fn daily_rotation_names_segment_after_its_day() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.log");
+        let sink = FileSink::new(&path).unwrap().with_rotation(Rotation::Daily);
+        let day_one = UNIX_EPOCH + Duration::from_secs(20_000 * DAY);
+        let day_two = day_one + Duration::from_secs(DAY);
+        sink.state.lock().unwrap().day = days_since_epoch(day_one);
+
+        sink.write_line(&test_log("first"), day_one).unwrap();
+        sink.write_line(&test_log("second"), day_one).unwrap();
+        sink.write_line(&test_log("third"), day_two).unwrap();
+
+        assert_eq!(
+            file_names(dir.path()),
+            vec!["app.log".to_string(), "app.log.2024-10-04.1".to_string()]
+        );
+        let segment = dir.path().join("app.log.2024-10-04.1");
+        assert_eq!(fs::read_to_string(segment).unwrap().lines().count(), 2);
+        assert_eq!(
+            fs::read_to_string(&path).unwrap().trim_end(),
+            test_log("third").to_string()
+        );
+    }
+
+    #[cfg(feature = "compression")]
+    #[test]
+    fn compression_gzips_rotated_segments() {
+        use flate2::read::GzDecoder;
+        use std::io::Read;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.log");
+        let sink = FileSink::new(&path)
+            .unwrap()
+            .with_rotation(Rotation::Size(line_len()))
+            .with_compression(true);
+        let now = SystemTime::now();
+        let today = format_date(days_since_epoch(now));
+
+        sink.write_line(&test_log("first"), now).unwrap();
+        sink.write_line(&test_log("second"), now).unwrap();
+
+        let segment = dir.path().join(format!("app.log.{}.1.gz", today));
+        assert_eq!(
+            file_names(dir.path()),
+            vec!["app.log".to_string(), format!("app.log.{}.1.gz", today)]
+        );
+        let mut decoded = String::new();
+        GzDecoder::new(File::open(segment).unwrap())
+            .read_to_string(&mut decoded)
+            .unwrap();
+        assert_eq!(decoded, format!("{}\n", test_log("first")));
+    }
+
+    #[test]
+    fn retention_keeps_newest_segments() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.log");
+        let sink = FileSink::new(&path)
+            .unwrap()
+            .with_rotation(Rotation::Size(line_len()))
+            .with_retention(2);
+        let now = SystemTime::now();
+        let today = format_date(days_since_epoch(now));
+
+        for _ in 0..12 {
+            sink.write_line(&test_log("x"), now).unwrap();
+        }
+
+        assert_eq!(
+            file_names(dir.path()),
+            vec![
+                "app.log".to_string(),
+                format!("app.log.{}.10", today),
+                format!("app.log.{}.11", today),
+            ]
+        );
+    }
+
+    #[test]
+    fn retention_ignores_unrelated_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.log");
+        fs::write(dir.path().join("app.log.bak"), "keep").unwrap();
+        fs::write(dir.path().join("other.log.2024-01-01.1"), "keep").unwrap();
+        let sink = FileSink::new(&path)
+            .unwrap()
+            .with_rotation(Rotation::Size(line_len()))
+            .with_retention(0);
+
+        sink.write_line(&test_log("first"), SystemTime::now())
+            .unwrap();
+        sink.write_line(&test_log("second"), SystemTime::now())
+            .unwrap();
+
+        assert_eq!(
+            file_names(dir.path()),
+            vec![
+                "app.log".to_string(),
+                "app.log.bak".to_string(),
+                "other.log.2024-01-01.1".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn segment_key_parses_date_and_counter() {
+        assert_eq!(
+            segment_key("2024-01-02.3"),
+            Some(("2024-01-02".to_string(), 3))
+        );
+        assert_eq!(segment_key("bak"), None);
+        assert_eq!(segment_key("2024-01-02.x"), None);
+        assert_eq!(segment_key("20240102xx.1"), None);
+    }
+}
@@ -0,0 +1,182 @@
+use crate::r#struct::betterstack_log_schema::BetterStackLogSchema;
+use crate::r#struct::log_level::LogLevel;
+use crate::time::format_rfc3339;
+use serde_json::Value;
+use std::env;
+use std::io::{self, IsTerminal};
+
+/// Keys rendered in dedicated columns, or constant for a logger, and left out of the fields.
+const PRETTY_SKIPPED_KEYS: [&str; 8] = [
+    "dt",
+    "level",
+    "message",
+    "context",
+    "env",
+    "app_version",
+    "host",
+    "thread",
+];
+
+const RESET: &str = "\x1b[0m";
+const DIM: &str = "\x1b[2m";
+
+/// Turns a record into the single line `ConsoleSink` prints.
+pub trait ConsoleFormatter: Send + Sync {
+    fn format(&self, log: &BetterStackLogSchema) -> String;
+}
+
+/// Compact JSON, identical to the record's `Display`.
+#[derive(Default)]
+pub struct JsonFormatter;
+
+impl ConsoleFormatter for JsonFormatter {
+    fn format(&self, log: &BetterStackLogSchema) -> String {
+        log.to_string()
+    }
+}
+
+/// Aligned `timestamp LEVEL message  context=… key=value` lines for local development.
+pub struct PrettyFormatter {
+    color: bool,
+}
+
+impl PrettyFormatter {
+    pub fn new(color: bool) -> Self {
+        Self { color }
+    }
+}
+
+impl Default for PrettyFormatter {
+    /// Colors only when both stdout and stderr are terminals and `NO_COLOR` is unset.
+    fn default() -> Self {
+        Self::new(is_terminal() && env::var_os("NO_COLOR").is_none())
+    }
+}
+
+impl ConsoleFormatter for PrettyFormatter {
+    fn format(&self, log: &BetterStackLogSchema) -> String {
+        let timestamp = format_rfc3339(log.dt);
+        let level = format!("{:<5}", log.level.to_string().to_uppercase());
+        let mut line = if self.color {
+            format!(
+                "{DIM}{timestamp}{RESET} {}{level}{RESET} {}",
+                level_color(&log.level),
+                log.message
+            )
+        } else {
+            format!("{timestamp} {level} {}", log.message)
+        };
+
+        let mut fields = vec![("context".to_string(), log.context.clone())];
+        if let Ok(Value::Object(map)) = serde_json::to_value(log) {
+            fields.extend(
+                map.into_iter()
+                    .filter(|(key, _)| !PRETTY_SKIPPED_KEYS.contains(&key.as_str()))
+                    .map(|(key, value)| (key, field_text(value))),
+            );
+        }
+
+        for (key, value) in fields.iter().filter(|(_, value)| !value.is_empty()) {
+            if self.color {
+                line.push_str(&format!("  {DIM}{key}={RESET}{value}"));
+            } else {
+                line.push_str(&format!("  {key}={value}"));
+            }
+        }
+        line
+    }
+}
+
+/// Pretty output on a terminal, JSON when piped into another program or a file.
+pub(crate) fn auto_formatter() -> Box<dyn ConsoleFormatter> {
+    if is_terminal() {
+        Box::new(PrettyFormatter::default())
+    } else {
+        Box::new(JsonFormatter)
+    }
+}
+
+fn is_terminal() -> bool {
+    io::stdout().is_terminal() && io::stderr().is_terminal()
+}
+
+fn level_color(level: &LogLevel) -> &'static str {
+    match level {
+        LogLevel::Debug => "\x1b[34m",
+        LogLevel::Info => "\x1b[32m",
+        LogLevel::Warn => "\x1b[33m",
+        LogLevel::Error => "\x1b[1;31m",
+    }
+}
+
+/// Strings unquoted, everything else as compact JSON.
+fn field_text(value: Value) -> String {
+    match value {
+        Value::String(text) => text,
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    fn test_log(level: LogLevel) -> BetterStackLogSchema {
+        BetterStackLogSchema {
+            dt: UNIX_EPOCH + Duration::from_millis(1_728_030_600_250),
+            context: "main.rs:10".to_string(),
+            ..BetterStackLogSchema::fixture(level, "server started")
+        }
+    }
+
+    #[test]
+    fn json_matches_display() {
+        let log = test_log(LogLevel::Info);
+        assert_eq!(JsonFormatter.format(&log), log.to_string());
+    }
+
+    #[test]
+    fn pretty_without_color() {
+        let line = PrettyFormatter::new(false).format(&test_log(LogLevel::Info));
+        assert_eq!(
+            line,
+            "2024-10-04T08:30:00.250Z INFO  server started  context=main.rs:10"
+        );
+    }
+
+    #[test]
+    fn pretty_aligns_levels() {
+        let formatter = PrettyFormatter::new(false);
+        let info = formatter.format(&test_log(LogLevel::Info));
+        let error = formatter.format(&test_log(LogLevel::Error));
+
+        assert_eq!(info.find("server"), error.find("server"));
+        assert!(error.contains(" ERROR server started"));
+    }
+
+    #[test]
+    fn pretty_omits_empty_context() {
+        let mut log = test_log(LogLevel::Warn);
+        log.context = String::new();
+
+        let line = PrettyFormatter::new(false).format(&log);
+        assert_eq!(line, "2024-10-04T08:30:00.250Z WARN  server started");
+    }
+
+    #[test]
+    fn pretty_with_color_wraps_level_in_ansi_codes() {
+        let line = PrettyFormatter::new(true).format(&test_log(LogLevel::Error));
+
+        assert!(line.contains("\x1b[1;31mERROR\x1b[0m"));
+        assert!(line.ends_with("main.rs:10"));
+        assert!(line.contains(RESET));
+    }
+
+    #[test]
+    fn field_text_unquotes_strings_only() {
+        assert_eq!(field_text(Value::String("a b".to_string())), "a b");
+        assert_eq!(field_text(serde_json::json!(42)), "42");
+        assert_eq!(field_text(serde_json::json!(["x"])), "[\"x\"]");
+    }
+}
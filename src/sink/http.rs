@@ -0,0 +1,534 @@
+use super::batch::{BatchConfig, Batcher};
+use super::{send_batches, HttpPost, Sink, SinkFuture};
+use crate::http_client::{
+    service, HttpClient, HttpRequest, HttpResponse, LogtailError, ReqwestClient,
+};
+use crate::r#struct::betterstack_log_schema::BetterStackLogSchema;
+use crate::r#struct::delivery_config::DeliveryConfig;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+
+/// How `HttpSink` authenticates its requests.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum HttpAuth {
+    #[default]
+    None,
+    /// `Authorization: Bearer <token>`.
+    Bearer(String),
+    /// `Authorization: Basic <base64 of username:password>`.
+    Basic { username: String, password: String },
+    /// An API key header, such as Datadog's `DD-API-KEY`.
+    Header { name: String, value: String },
+}
+
+impl HttpAuth {
+    /// The header carrying the credentials, if any.
+    pub(crate) fn header(&self) -> Option<(String, String)> {
+        match self {
+            HttpAuth::None => None,
+            HttpAuth::Bearer(token) => {
+                Some(("Authorization".to_string(), format!("Bearer {}", token)))
+            }
+            HttpAuth::Basic { username, password } => {
+                let credentials = STANDARD.encode(format!("{}:{}", username, password));
+                Some((
+                    "Authorization".to_string(),
+                    format!("Basic {}", credentials),
+                ))
+            }
+            HttpAuth::Header { name, value } => Some((name.clone(), value.clone())),
+        }
+    }
+}
+
+/// Shape of `HttpSink` request bodies.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum Envelope {
+    /// A single JSON object, one request per record.
+    Object,
+    /// A JSON array of records.
+    #[default]
+    Array,
+    /// The array of records under a key, e.g. `{"logs": [...]}`.
+    Wrapped(String),
+    /// Newline-delimited JSON, one record per line.
+    NdJson,
+    /// An Elasticsearch `_bulk` body, each record preceded by an `index` action. A response
+    /// flagging `errors` fails the write, with the first rejected record's reason.
+    ElasticBulk { index: String },
+}
+
+impl Envelope {
+    fn content_type(&self) -> &'static str {
+        match self {
+            Envelope::Object | Envelope::Array | Envelope::Wrapped(_) => "application/json",
+            Envelope::NdJson | Envelope::ElasticBulk { .. } => "application/x-ndjson",
+        }
+    }
+}
+
+/// Ships records as JSON to any HTTP log backend, such as Elasticsearch, Datadog or a
+/// self-hosted collector.
+///
+/// Records are serialized like [`BetterStackLogSchema`], with top-level fields renamed
+/// per [`HttpSink::with_field_name`], and wrapped in the configured [`Envelope`]. Header
+/// values may hold `{env}`, `{app_version}` and `{service}` placeholders. The circuit
+/// breaker, rate limit and sampling of its [`DeliveryConfig`] only apply to BetterStack.
+pub struct HttpSink<C: HttpClient = ReqwestClient> {
+    client: C,
+    url: String,
+    auth: HttpAuth,
+    headers: Vec<(String, String)>,
+    field_names: BTreeMap<String, String>,
+    envelope: Envelope,
+    delivery: DeliveryConfig,
+    batcher: Batcher,
+}
+
+impl<C: HttpClient> HttpSink<C> {
+    /// Posts every record to `url` on its own, as a JSON array of one.
+    pub fn new(client: C, url: impl Into<String>) -> Self {
+        Self {
+            client,
+            url: url.into(),
+            auth: HttpAuth::default(),
+            headers: Vec::new(),
+            field_names: BTreeMap::new(),
+            envelope: Envelope::default(),
+            delivery: DeliveryConfig::default(),
+            batcher: Batcher::new(BatchConfig::none()),
+        }
+    }
+
+    pub fn with_auth(mut self, auth: HttpAuth) -> Self {
+        self.auth = auth;
+        self
+    }
+
+    /// Adds a header to every request, `template` placeholders filled from the records.
+    pub fn with_header(mut self, name: impl Into<String>, template: impl Into<String>) -> Self {
+        self.headers.push((name.into(), template.into()));
+        self
+    }
+
+    /// Sends the top-level `field` as `name`, e.g. `dt` as `@timestamp`.
+    pub fn with_field_name(mut self, field: impl Into<String>, name: impl Into<String>) -> Self {
+        self.field_names.insert(field.into(), name.into());
+        self
+    }
+
+    pub fn with_envelope(mut self, envelope: Envelope) -> Self {
+        self.envelope = envelope;
+        self
+    }
+
+    pub fn with_delivery(mut self, delivery: DeliveryConfig) -> Self {
+        self.delivery = delivery;
+        self
+    }
+
+    /// Buffers records and sends them together, see [`BatchConfig`].
+    pub fn with_batch(mut self, batch: BatchConfig) -> Self {
+        self.batcher = Batcher::new(batch);
+        self
+    }
+
+    fn encode(&self, logs: &[BetterStackLogSchema]) -> Result<Vec<u8>, LogtailError> {
+        let records = logs
+            .iter()
+            .map(|log| self.record(log))
+            .collect::<Result<Vec<_>, _>>()?;
+        let body = match &self.envelope {
+            // batches of an object envelope hold a single record
+            Envelope::Object => serde_json::to_vec(&records[0])?,
+            Envelope::Array => serde_json::to_vec(&records)?,
+            Envelope::Wrapped(key) => serde_json::to_vec(&json!({ key.as_str(): records }))?,
+            Envelope::NdJson => ndjson(records.iter())?,
+            Envelope::ElasticBulk { index } => {
+                let action = json!({ "index": { "_index": index } });
+                ndjson(records.iter().flat_map(|record| [&action, record]))?
+            }
+        };
+        Ok(body)
+    }
+
+    /// The serialized record with its fields renamed.
+    fn record(&self, log: &BetterStackLogSchema) -> Result<Value, LogtailError> {
+        let fields: Map<String, Value> = serde_json::from_value(serde_json::to_value(log)?)?;
+        let renamed: Map<String, Value> = fields
+            .into_iter()
+            .map(|(key, value)| match self.field_names.get(&key) {
+                Some(name) => (name.clone(), value),
+                None => (key, value),
+            })
+            .collect();
+        Ok(Value::Object(renamed))
+    }
+
+    fn request_headers(&self, log: &BetterStackLogSchema) -> Vec<(String, String)> {
+        let mut headers: Vec<_> = self
+            .headers
+            .iter()
+            .map(|(name, template)| (name.clone(), expand(template, log)))
+            .collect();
+        headers.extend(self.auth.header());
+        headers
+    }
+}
+
+impl<C: HttpClient> HttpPost for HttpSink<C> {
+    fn delivery(&self) -> &DeliveryConfig {
+        &self.delivery
+    }
+
+    fn one_record_per_request(&self) -> bool {
+        self.envelope == Envelope::Object
+    }
+
+    fn post<'a>(&'a self, logs: &'a [BetterStackLogSchema]) -> SinkFuture<'a> {
+        Box::pin(async move {
+            let Some(first) = logs.first() else {
+                return Ok(());
+            };
+            let request = HttpRequest {
+                url: self.url.clone(),
+                body: self.encode(logs)?,
+                content_type: self.envelope.content_type().to_string(),
+                content_encoding: None,
+                headers: self.request_headers(first),
+            };
+            let response = service::deliver(&self.client, &self.delivery, request).await?;
+            match self.envelope {
+                Envelope::ElasticBulk { .. } => bulk_result(&response),
+                _ => Ok(()),
+            }
+        })
+    }
+}
+
+impl<C: HttpClient> Sink for HttpSink<C> {
+    fn write<'a>(&'a self, log: &'a BetterStackLogSchema) -> SinkFuture<'a> {
+        Box::pin(async move {
+            match self.batcher.push(log) {
+                Some(batch) => send_batches(self, batch).await,
+                None => Ok(()),
+            }
+        })
+    }
+
+    fn flush(&self) -> SinkFuture<'_> {
+        Box::pin(send_batches(self, self.batcher.take()))
+    }
+}
+
+/// Fills the `{env}`, `{app_version}` and `{service}` placeholders of `template`.
+fn expand(template: &str, log: &BetterStackLogSchema) -> String {
+    template
+        .replace("{env}", &log.env.to_string())
+        .replace("{app_version}", &log.app_version)
+        .replace("{service}", log.service.as_deref().unwrap_or_default())
+}
+
+/// Fails when an Elasticsearch `_bulk` response reports rejected records, which it does
+/// with a success status.
+fn bulk_result(response: &HttpResponse) -> Result<(), LogtailError> {
+    let Ok(body) = serde_json::from_slice::<Value>(&response.body) else {
+        return Ok(());
+    };
+    if body["errors"] != true {
+        return Ok(());
+    }
+    let items = body["items"]
+        .as_array()
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+    let rejected: Vec<_> = items
+        .iter()
+        .filter_map(|item| item.as_object()?.values().next()?.get("error"))
+        .collect();
+    let reason = rejected
+        .first()
+        .and_then(|error| error["reason"].as_str())
+        .unwrap_or("unknown reason");
+    Err(LogtailError::Http {
+        status: response.status,
+        message: format!(
+            "bulk request rejected {} of {} records: {}",
+            rejected.len(),
+            items.len(),
+            reason
+        ),
+    })
+}
+
+fn ndjson<'a>(values: impl Iterator<Item = &'a Value>) -> Result<Vec<u8>, LogtailError> {
+    let mut body = Vec::new();
+    for value in values {
+        serde_json::to_writer(&mut body, value)?;
+        body.push(b'\n');
+    }
+    Ok(body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::r#struct::env_config::{EnvConfig, EnvEnum};
+    use crate::r#struct::log_level::LogLevel;
+    use crate::test_util::{RecordingTransport, ScriptedResponse};
+    use crate::{LogSchema, Logger};
+    use std::time::Duration;
+
+    const URL: &str = "http://localhost:9200/_bulk";
+
+    fn test_log(message: &str) -> BetterStackLogSchema {
+        let config =
+            EnvConfig::from_values("1.0.0".to_string(), EnvEnum::QA, "token".to_string(), false)
+                .with_service("billing");
+        BetterStackLogSchema::new(&config, LogLevel::Info, message.to_string(), String::new())
+    }
+
+    fn sink(transport: &RecordingTransport) -> HttpSink<RecordingTransport> {
+        HttpSink::new(transport.clone(), URL)
+    }
+
+    fn body(transport: &RecordingTransport, index: usize) -> Value {
+        serde_json::from_slice(&transport.requests()[index].body).unwrap()
+    }
+
+    fn lines(transport: &RecordingTransport) -> Vec<Value> {
+        let body = String::from_utf8(transport.requests()[0].body.clone()).unwrap();
+        body.lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn posts_array_of_records() {
+        let transport = RecordingTransport::new();
+
+        sink(&transport).write(&test_log("hello")).await.unwrap();
+
+        let request = &transport.requests()[0];
+        assert_eq!(request.url, URL);
+        assert_eq!(request.content_type, "application/json");
+        assert_eq!(request.header("authorization"), None);
+        assert_eq!(body(&transport, 0)[0]["message"], "hello");
+    }
+
+    #[tokio::test]
+    async fn object_envelope_sends_one_record_per_request() {
+        let transport = RecordingTransport::new();
+        let sink = sink(&transport)
+            .with_envelope(Envelope::Object)
+            .with_batch(BatchConfig {
+                max_records: 2,
+                max_delay: Duration::from_secs(60),
+            });
+
+        sink.write(&test_log("one")).await.unwrap();
+        sink.write(&test_log("two")).await.unwrap();
+
+        assert_eq!(transport.request_count(), 2);
+        assert_eq!(body(&transport, 0)["message"], "one");
+        assert_eq!(body(&transport, 1)["message"], "two");
+    }
+
+    #[tokio::test]
+    async fn wrapped_envelope_nests_records_under_key() {
+        let transport = RecordingTransport::new();
+        let sink = sink(&transport).with_envelope(Envelope::Wrapped("logs".to_string()));
+
+        sink.write(&test_log("hello")).await.unwrap();
+
+        assert_eq!(body(&transport, 0)["logs"][0]["message"], "hello");
+    }
+
+    #[tokio::test]
+    async fn ndjson_envelope_writes_lines() {
+        let transport = RecordingTransport::new();
+        let sink = sink(&transport)
+            .with_envelope(Envelope::NdJson)
+            .with_batch(BatchConfig {
+                max_records: 2,
+                max_delay: Duration::from_secs(60),
+            });
+
+        sink.write(&test_log("one")).await.unwrap();
+        sink.write(&test_log("two")).await.unwrap();
+
+        assert_eq!(transport.requests()[0].content_type, "application/x-ndjson");
+        let messages: Vec<_> = lines(&transport)
+            .iter()
+            .map(|line| line["message"].clone())
+            .collect();
+        assert_eq!(messages, vec!["one", "two"]);
+    }
+
+    #[tokio::test]
+    async fn elastic_bulk_precedes_records_with_index_action() {
+        let transport = RecordingTransport::new();
+        let sink = sink(&transport)
+            .with_envelope(Envelope::ElasticBulk {
+                index: "logs-app".to_string(),
+            })
+            .with_field_name("dt", "@timestamp");
+
+        sink.write(&test_log("hello")).await.unwrap();
+
+        let lines = lines(&transport);
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0], json!({ "index": { "_index": "logs-app" } }));
+        assert_eq!(lines[1]["message"], "hello");
+        assert!(lines[1]["@timestamp"].is_string());
+        assert!(lines[1].get("dt").is_none());
+    }
+
+    #[tokio::test]
+    async fn sends_auth_and_expanded_headers() {
+        let transport = RecordingTransport::new();
+        let sink = sink(&transport)
+            .with_auth(HttpAuth::Basic {
+                username: "elastic".to_string(),
+                password: "changeme".to_string(),
+            })
+            .with_header("X-Source", "{service}-{env}@{app_version}");
+
+        sink.write(&test_log("hello")).await.unwrap();
+
+        let request = &transport.requests()[0];
+        assert_eq!(
+            request.header("authorization"),
+            Some("Basic ZWxhc3RpYzpjaGFuZ2VtZQ==")
+        );
+        assert_eq!(request.header("x-source"), Some("billing-qa@1.0.0"));
+    }
+
+    #[tokio::test]
+    async fn sends_bearer_and_api_key_headers() {
+        let transport = RecordingTransport::new();
+
+        sink(&transport)
+            .with_auth(HttpAuth::Bearer("secret".to_string()))
+            .write(&test_log("one"))
+            .await
+            .unwrap();
+        sink(&transport)
+            .with_auth(HttpAuth::Header {
+                name: "DD-API-KEY".to_string(),
+                value: "key".to_string(),
+            })
+            .write(&test_log("two"))
+            .await
+            .unwrap();
+
+        let requests = transport.requests();
+        assert_eq!(requests[0].header("authorization"), Some("Bearer secret"));
+        assert_eq!(requests[1].header("dd-api-key"), Some("key"));
+    }
+
+    #[tokio::test]
+    async fn flush_sends_partial_batch() {
+        let transport = RecordingTransport::new();
+        let sink = sink(&transport).with_batch(BatchConfig::default());
+
+        sink.write(&test_log("one")).await.unwrap();
+        assert_eq!(transport.request_count(), 0);
+        sink.flush().await.unwrap();
+        sink.flush().await.unwrap();
+
+        assert_eq!(transport.request_count(), 1);
+        assert_eq!(body(&transport, 0)[0]["message"], "one");
+    }
+
+    #[tokio::test]
+    async fn logger_flush_sends_buffered_records() {
+        let transport = RecordingTransport::new();
+        let config =
+            EnvConfig::from_values("1.0.0".to_string(), EnvEnum::QA, "token".to_string(), false);
+        let logger = Logger::with_client(config, RecordingTransport::new()).with_sink(
+            sink(&transport).with_batch(BatchConfig::default()),
+            LogLevel::Debug,
+        );
+
+        logger
+            .info(LogSchema {
+                message: "buffered".to_string(),
+                context: String::new(),
+            })
+            .await;
+        assert_eq!(transport.request_count(), 0);
+        logger.flush().await;
+
+        assert_eq!(body(&transport, 0)[0]["message"], "buffered");
+    }
+
+    #[tokio::test]
+    async fn retries_per_delivery_config() {
+        let transport = RecordingTransport::new();
+        transport.then_respond(ScriptedResponse::Status(503));
+        let sink = sink(&transport).with_delivery(DeliveryConfig {
+            retries: 1,
+            retry_backoff: Duration::from_millis(1),
+            ..DeliveryConfig::default()
+        });
+
+        sink.write(&test_log("hello")).await.unwrap();
+
+        assert_eq!(transport.request_count(), 2);
+    }
+
+    #[tokio::test]
+    async fn surfaces_http_errors() {
+        let transport = RecordingTransport::new();
+        transport.always_respond(ScriptedResponse::Status(400));
+
+        let result = sink(&transport).write(&test_log("hello")).await;
+
+        assert!(matches!(
+            result,
+            Err(LogtailError::Http { status: 400, .. })
+        ));
+    }
+
+    fn response(body: Value) -> HttpResponse {
+        HttpResponse {
+            status: 200,
+            headers: Vec::new(),
+            body: serde_json::to_vec(&body).unwrap(),
+        }
+    }
+
+    #[test]
+    fn bulk_result_fails_on_rejected_records() {
+        let body = json!({
+            "errors": true,
+            "items": [
+                { "index": { "status": 201 } },
+                { "index": { "status": 400, "error": { "reason": "mapper_parsing_exception" } } },
+            ],
+        });
+
+        let result = bulk_result(&response(body));
+
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "HTTP 200: bulk request rejected 1 of 2 records: mapper_parsing_exception"
+        );
+    }
+
+    #[test]
+    fn bulk_result_accepts_clean_responses() {
+        let body = json!({ "errors": false, "items": [{ "index": { "status": 201 } }] });
+
+        assert!(bulk_result(&response(body)).is_ok());
+        assert!(bulk_result(&HttpResponse {
+            status: 200,
+            headers: Vec::new(),
+            body: Vec::new(),
+        })
+        .is_ok());
+    }
+}
@@ -0,0 +1,352 @@
+use super::batch::{BatchConfig, Batcher};
+use super::http::HttpAuth;
+use super::{send_batches, HttpPost, Sink, SinkFuture};
+use crate::http_client::{service, HttpClient, HttpRequest, ReqwestClient};
+use crate::r#struct::betterstack_log_schema::BetterStackLogSchema;
+use crate::r#struct::delivery_config::DeliveryConfig;
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::time::UNIX_EPOCH;
+
+const PUSH_PATH: &str = "/loki/api/v1/push";
+
+/// A record field that becomes a Loki stream label.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LokiLabel {
+    /// `env`, e.g. `prod`.
+    Env,
+    AppVersion,
+    /// `level`, e.g. `error`.
+    Level,
+    /// `service`, left out when the record has none.
+    Service,
+    /// Every `EnvConfig` label under its own key.
+    Labels,
+}
+
+type StreamLabels = BTreeMap<String, String>;
+
+/// Pushes records to Grafana Loki through its `/loki/api/v1/push` JSON API.
+///
+/// Every record is pushed as soon as it is written, unless batched with
+/// [`LokiSink::with_batch`]. Records are grouped into one stream per distinct set of
+/// labels, each entry holding the record's nanosecond timestamp and its JSON line.
+pub struct LokiSink<C: HttpClient = ReqwestClient> {
+    client: C,
+    url: String,
+    labels: Vec<LokiLabel>,
+    auth: HttpAuth,
+    tenant: Option<String>,
+    delivery: DeliveryConfig,
+    batcher: Batcher,
+}
+
+impl<C: HttpClient> LokiSink<C> {
+    /// Pushes to the Loki server at `url`, e.g. `http://localhost:3100`.
+    pub fn new(client: C, url: impl AsRef<str>) -> Self {
+        Self {
+            client,
+            url: format!("{}{}", url.as_ref().trim_end_matches('/'), PUSH_PATH),
+            labels: vec![
+                LokiLabel::Env,
+                LokiLabel::AppVersion,
+                LokiLabel::Level,
+                LokiLabel::Service,
+                LokiLabel::Labels,
+            ],
+            auth: HttpAuth::default(),
+            tenant: None,
+            delivery: DeliveryConfig::default(),
+            batcher: Batcher::new(BatchConfig::none()),
+        }
+    }
+
+    /// Keys streams by `labels` instead of all of them.
+    pub fn with_labels(mut self, labels: impl IntoIterator<Item = LokiLabel>) -> Self {
+        self.labels = labels.into_iter().collect();
+        self
+    }
+
+    /// E.g. basic auth with a Grafana Cloud user and token.
+    pub fn with_auth(mut self, auth: HttpAuth) -> Self {
+        self.auth = auth;
+        self
+    }
+
+    /// Sent as `X-Scope-OrgID` to a multi-tenant Loki.
+    pub fn with_tenant(mut self, tenant: impl Into<String>) -> Self {
+        self.tenant = Some(tenant.into());
+        self
+    }
+
+    pub fn with_delivery(mut self, delivery: DeliveryConfig) -> Self {
+        self.delivery = delivery;
+        self
+    }
+
+    pub fn with_batch(mut self, batch: BatchConfig) -> Self {
+        self.batcher = Batcher::new(batch);
+        self
+    }
+
+    /// `{"streams": [{"stream": {labels}, "values": [["<ns>", "<line>"], ...]}]}`, streams
+    /// in the order they first appear.
+    fn push_request(&self, logs: &[BetterStackLogSchema]) -> serde_json::Value {
+        let mut streams: Vec<(StreamLabels, Vec<[String; 2]>)> = Vec::new();
+        for log in logs {
+            let labels = self.stream_labels(log);
+            let entry = [timestamp(log), log.to_string()];
+            match streams.iter_mut().find(|(stream, _)| *stream == labels) {
+                Some((_, values)) => values.push(entry),
+                None => streams.push((labels, vec![entry])),
+            }
+        }
+        let streams: Vec<_> = streams
+            .into_iter()
+            .map(|(stream, values)| json!({ "stream": stream, "values": values }))
+            .collect();
+        json!({ "streams": streams })
+    }
+
+    fn stream_labels(&self, log: &BetterStackLogSchema) -> StreamLabels {
+        let mut labels = StreamLabels::new();
+        for label in &self.labels {
+            match label {
+                LokiLabel::Env => {
+                    labels.insert("env".to_string(), log.env.to_string());
+                }
+                LokiLabel::AppVersion => {
+                    labels.insert("app_version".to_string(), log.app_version.clone());
+                }
+                LokiLabel::Level => {
+                    labels.insert("level".to_string(), log.level.to_string());
+                }
+                LokiLabel::Service => {
+                    if let Some(service) = &log.service {
+                        labels.insert("service".to_string(), service.clone());
+                    }
+                }
+                LokiLabel::Labels => {
+                    for (key, value) in &log.labels {
+                        labels.insert(label_name(key), value.clone());
+                    }
+                }
+            }
+        }
+        labels
+    }
+}
+
+impl<C: HttpClient> HttpPost for LokiSink<C> {
+    fn delivery(&self) -> &DeliveryConfig {
+        &self.delivery
+    }
+
+    fn post<'a>(&'a self, logs: &'a [BetterStackLogSchema]) -> SinkFuture<'a> {
+        Box::pin(async move {
+            let mut headers: Vec<_> = self.auth.header().into_iter().collect();
+            if let Some(tenant) = &self.tenant {
+                headers.push(("X-Scope-OrgID".to_string(), tenant.clone()));
+            }
+            let request = HttpRequest {
+                url: self.url.clone(),
+                body: serde_json::to_vec(&self.push_request(logs))?,
+                content_type: "application/json".to_string(),
+                content_encoding: None,
+                headers,
+            };
+            service::deliver(&self.client, &self.delivery, request).await?;
+            Ok(())
+        })
+    }
+}
+
+impl<C: HttpClient> Sink for LokiSink<C> {
+    fn write<'a>(&'a self, log: &'a BetterStackLogSchema) -> SinkFuture<'a> {
+        Box::pin(async move {
+            match self.batcher.push(log) {
+                Some(batch) => send_batches(self, batch).await,
+                None => Ok(()),
+            }
+        })
+    }
+
+    fn flush(&self) -> SinkFuture<'_> {
+        Box::pin(send_batches(self, self.batcher.take()))
+    }
+}
+
+/// Nanoseconds since the Unix epoch, as a string.
+fn timestamp(log: &BetterStackLogSchema) -> String {
+    let since_epoch = log.dt.duration_since(UNIX_EPOCH).unwrap_or_default();
+    since_epoch.as_nanos().to_string()
+}
+
+/// `key` as a valid Prometheus label name, `[a-zA-Z_][a-zA-Z0-9_]*`.
+fn label_name(key: &str) -> String {
+    let mut name: String = key
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    if !name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+        name.insert(0, '_');
+    }
+    name
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http_client::{LogtailError, ReqwestClient};
+    use crate::r#struct::env_config::{EnvConfig, EnvEnum};
+    use crate::r#struct::log_level::LogLevel;
+    use crate::test_util::{FakeIngestServer, RecordingTransport, ScriptedResponse};
+    use serde_json::Value;
+    use std::time::Duration;
+
+    fn test_log(level: LogLevel, message: &str) -> BetterStackLogSchema {
+        let config =
+            EnvConfig::from_values("1.0.0".to_string(), EnvEnum::QA, "token".to_string(), false)
+                .with_service("billing")
+                .with_label("k8s.cluster", "eu-1");
+        let mut log = BetterStackLogSchema::new(&config, level, message.to_string(), String::new());
+        log.dt = UNIX_EPOCH + Duration::from_millis(1_728_030_600_250);
+        log
+    }
+
+    fn unbatched(transport: &RecordingTransport) -> LokiSink<RecordingTransport> {
+        LokiSink::new(transport.clone(), "http://loki:3100/")
+    }
+
+    fn body(transport: &RecordingTransport) -> Value {
+        serde_json::from_slice(&transport.requests()[0].body).unwrap()
+    }
+
+    #[tokio::test]
+    async fn pushes_stream_with_nanosecond_entries() {
+        let transport = RecordingTransport::new();
+        let log = test_log(LogLevel::Info, "hello");
+
+        unbatched(&transport).write(&log).await.unwrap();
+
+        let request = &transport.requests()[0];
+        assert_eq!(request.url, "http://loki:3100/loki/api/v1/push");
+        assert_eq!(request.content_type, "application/json");
+        assert_eq!(
+            body(&transport),
+            json!({ "streams": [{
+                "stream": {
+                    "env": "qa",
+                    "app_version": "1.0.0",
+                    "level": "info",
+                    "service": "billing",
+                    "k8s_cluster": "eu-1",
+                },
+                "values": [["1728030600250000000", log.to_string()]],
+            }] })
+        );
+    }
+
+    #[tokio::test]
+    async fn groups_batch_into_streams_by_labels() {
+        let transport = RecordingTransport::new();
+        let sink = LokiSink::new(transport.clone(), "http://loki:3100")
+            .with_labels([LokiLabel::Level])
+            .with_batch(BatchConfig {
+                max_records: 3,
+                max_delay: Duration::from_secs(60),
+            });
+
+        sink.write(&test_log(LogLevel::Info, "one")).await.unwrap();
+        sink.write(&test_log(LogLevel::Error, "two")).await.unwrap();
+        assert_eq!(transport.request_count(), 0);
+        sink.write(&test_log(LogLevel::Info, "three"))
+            .await
+            .unwrap();
+
+        let streams = body(&transport)["streams"].as_array().unwrap().clone();
+        assert_eq!(transport.request_count(), 1);
+        assert_eq!(streams.len(), 2);
+        assert_eq!(streams[0]["stream"], json!({ "level": "info" }));
+        assert_eq!(streams[0]["values"].as_array().unwrap().len(), 2);
+        assert_eq!(streams[1]["stream"], json!({ "level": "error" }));
+    }
+
+    #[tokio::test]
+    async fn flush_pushes_partial_batch() {
+        let transport = RecordingTransport::new();
+        let sink =
+            LokiSink::new(transport.clone(), "http://loki:3100").with_batch(BatchConfig::default());
+
+        sink.write(&test_log(LogLevel::Info, "one")).await.unwrap();
+        assert_eq!(transport.request_count(), 0);
+        sink.flush().await.unwrap();
+
+        assert_eq!(
+            body(&transport)["streams"][0]["values"][0][0],
+            "1728030600250000000"
+        );
+    }
+
+    #[tokio::test]
+    async fn sends_tenant_and_auth() {
+        let transport = RecordingTransport::new();
+        let sink = unbatched(&transport)
+            .with_tenant("team-a")
+            .with_auth(HttpAuth::Basic {
+                username: "user".to_string(),
+                password: "pass".to_string(),
+            });
+
+        sink.write(&test_log(LogLevel::Info, "hello"))
+            .await
+            .unwrap();
+
+        let request = &transport.requests()[0];
+        assert_eq!(request.header("x-scope-orgid"), Some("team-a"));
+        assert_eq!(request.header("authorization"), Some("Basic dXNlcjpwYXNz"));
+    }
+
+    #[tokio::test]
+    async fn surfaces_rejected_pushes() {
+        let transport = RecordingTransport::new();
+        transport.always_respond(ScriptedResponse::Status(400));
+
+        let result = unbatched(&transport)
+            .write(&test_log(LogLevel::Info, "hello"))
+            .await;
+
+        assert!(matches!(
+            result,
+            Err(LogtailError::Http { status: 400, .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn pushes_to_local_endpoint() {
+        let server = FakeIngestServer::start("token").unwrap();
+        let sink = LokiSink::new(ReqwestClient::default(), server.url())
+            .with_auth(HttpAuth::Bearer("token".to_string()))
+            .with_batch(BatchConfig::none());
+
+        sink.write(&test_log(LogLevel::Warn, "hello"))
+            .await
+            .unwrap();
+
+        let request = &server.requests()[0];
+        assert_eq!(request.path, PUSH_PATH);
+        assert_eq!(request.status, Some(202));
+        let body: Value = serde_json::from_slice(&request.body).unwrap();
+        assert_eq!(body["streams"][0]["stream"]["level"], "warn");
+        let line: Value =
+            serde_json::from_str(body["streams"][0]["values"][0][1].as_str().unwrap()).unwrap();
+        assert_eq!(line["message"], "hello");
+    }
+
+    #[test]
+    fn sanitizes_label_names() {
+        assert_eq!(label_name("region"), "region");
+        assert_eq!(label_name("k8s.cluster-name"), "k8s_cluster_name");
+        assert_eq!(label_name("1st"), "_1st");
+    }
+}
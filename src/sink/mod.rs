@@ -0,0 +1,102 @@
+mod batch;
+mod betterstack;
+mod console;
+mod file;
+mod format;
+mod http;
+mod loki;
+mod otlp;
+mod syslog;
+mod throttle;
+
+pub use batch::BatchConfig;
+pub use betterstack::BetterStackSink;
+pub use console::{ConsoleSink, ConsoleTarget};
+pub use file::{FileSink, Rotation};
+pub use format::{ConsoleFormatter, JsonFormatter, PrettyFormatter};
+pub use http::{Envelope, HttpAuth, HttpSink};
+pub use loki::{LokiLabel, LokiSink};
+pub use otlp::{OtlpProtocol, OtlpSink};
+pub use syslog::{Facility, SyslogSink, SyslogTransport};
+pub use throttle::{RateLimitConfig, SamplingConfig};
+
+use crate::http_client::{Encoding, LogtailError};
+use crate::r#struct::betterstack_log_schema::BetterStackLogSchema;
+use crate::r#struct::delivery_config::DeliveryConfig;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+/// Boxed future returned by [`Sink::write`], so sinks can be stored as trait objects.
+pub type SinkFuture<'a> = Pin<Box<dyn Future<Output = Result<(), LogtailError>> + Send + 'a>>;
+
+/// A destination `Logger` fans every record out to.
+///
+/// Failures are reported back to the logger, which hands them to its error hook and carries on:
+/// a broken sink must never stop the others from receiving the record.
+pub trait Sink: Send + Sync {
+    fn write<'a>(&'a self, log: &'a BetterStackLogSchema) -> SinkFuture<'a>;
+
+    /// Sends whatever the sink buffered, see [`Logger::flush`](crate::Logger::flush).
+    fn flush(&self) -> SinkFuture<'_> {
+        Box::pin(std::future::ready(Ok(())))
+    }
+}
+
+impl<S: Sink + ?Sized> Sink for Arc<S> {
+    fn write<'a>(&'a self, log: &'a BetterStackLogSchema) -> SinkFuture<'a> {
+        (**self).write(log)
+    }
+
+    fn flush(&self) -> SinkFuture<'_> {
+        (**self).flush()
+    }
+}
+
+/// A sink posting records over HTTP through [`deliver`](crate::http_client::service::deliver),
+/// so retries, the total timeout and size limits follow its [`DeliveryConfig`].
+pub(crate) trait HttpPost: Sync {
+    fn delivery(&self) -> &DeliveryConfig;
+
+    /// Whether every request holds a single record, whatever the batch size limit allows.
+    fn one_record_per_request(&self) -> bool {
+        false
+    }
+
+    /// Sends one request holding `logs`, which is never empty.
+    fn post<'a>(&'a self, logs: &'a [BetterStackLogSchema]) -> SinkFuture<'a>;
+}
+
+/// Sends `logs` in as many requests as `sink` and its size limits require, returning the
+/// first error once everything else has been sent.
+pub(crate) async fn send_batches(
+    sink: &impl HttpPost,
+    logs: Vec<BetterStackLogSchema>,
+) -> Result<(), LogtailError> {
+    let limits = &sink.delivery().size_limits;
+    let mut first_error = None;
+    let mut limited = Vec::with_capacity(logs.len());
+    for log in &logs {
+        match limits.limit(log, Encoding::Json) {
+            Ok(log) => limited.push(log),
+            Err(err) => {
+                first_error.get_or_insert(err);
+            }
+        }
+    }
+
+    let batches = if sink.one_record_per_request() {
+        limited.chunks(1).collect()
+    } else {
+        limits.split(&limited, Encoding::Json)?
+    };
+    for batch in batches {
+        if let Err(err) = sink.post(batch).await {
+            first_error.get_or_insert(err);
+        }
+    }
+    match first_error {
+        Some(err) => Err(err),
+        None => Ok(()),
+    }
+}
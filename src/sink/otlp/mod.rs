@@ -0,0 +1,240 @@
+mod model;
+mod protobuf;
+
+use super::batch::{BatchConfig, Batcher};
+use super::http::HttpAuth;
+use super::{send_batches, HttpPost, Sink, SinkFuture};
+use crate::http_client::{service, HttpClient, HttpRequest, ReqwestClient};
+use crate::r#struct::betterstack_log_schema::BetterStackLogSchema;
+use crate::r#struct::delivery_config::DeliveryConfig;
+use model::ExportLogsRequest;
+
+const LOGS_PATH: &str = "/v1/logs";
+
+/// Wire format of OTLP/HTTP export requests.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum OtlpProtocol {
+    /// `http/json`, readable and accepted by every OTLP/HTTP collector.
+    #[default]
+    HttpJson,
+    /// `http/protobuf`, the default of most OpenTelemetry SDKs.
+    HttpProtobuf,
+}
+
+impl OtlpProtocol {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            OtlpProtocol::HttpJson => "application/json",
+            OtlpProtocol::HttpProtobuf => "application/x-protobuf",
+        }
+    }
+}
+
+/// Exports records to an OpenTelemetry collector over OTLP/HTTP.
+///
+/// Each record becomes an OTLP `LogRecord` timestamped with its `dt`, with the level as
+/// severity, the message as body and every other field as a flattened attribute. Records
+/// are grouped under one resource per distinct service name, version, environment and set
+/// of labels. Like [`LokiSink`](super::LokiSink), every record is exported as soon as it is
+/// written unless batched with [`OtlpSink::with_batch`].
+pub struct OtlpSink<C: HttpClient = ReqwestClient> {
+    client: C,
+    url: String,
+    protocol: OtlpProtocol,
+    auth: HttpAuth,
+    delivery: DeliveryConfig,
+    batcher: Batcher,
+}
+
+impl<C: HttpClient> OtlpSink<C> {
+    /// Exports to the collector at `url`, e.g. `http://localhost:4318`.
+    pub fn new(client: C, url: impl AsRef<str>) -> Self {
+        Self {
+            client,
+            url: format!("{}{}", url.as_ref().trim_end_matches('/'), LOGS_PATH),
+            protocol: OtlpProtocol::default(),
+            auth: HttpAuth::default(),
+            delivery: DeliveryConfig::default(),
+            batcher: Batcher::new(BatchConfig::none()),
+        }
+    }
+
+    pub fn with_protocol(mut self, protocol: OtlpProtocol) -> Self {
+        self.protocol = protocol;
+        self
+    }
+
+    pub fn with_auth(mut self, auth: HttpAuth) -> Self {
+        self.auth = auth;
+        self
+    }
+
+    pub fn with_delivery(mut self, delivery: DeliveryConfig) -> Self {
+        self.delivery = delivery;
+        self
+    }
+
+    pub fn with_batch(mut self, batch: BatchConfig) -> Self {
+        self.batcher = Batcher::new(batch);
+        self
+    }
+}
+
+impl<C: HttpClient> HttpPost for OtlpSink<C> {
+    fn delivery(&self) -> &DeliveryConfig {
+        &self.delivery
+    }
+
+    fn post<'a>(&'a self, logs: &'a [BetterStackLogSchema]) -> SinkFuture<'a> {
+        Box::pin(async move {
+            let export = ExportLogsRequest::from_logs(logs)?;
+            let body = match self.protocol {
+                OtlpProtocol::HttpJson => serde_json::to_vec(&export.to_json())?,
+                OtlpProtocol::HttpProtobuf => protobuf::encode(&export),
+            };
+            let request = HttpRequest {
+                url: self.url.clone(),
+                body,
+                content_type: self.protocol.content_type().to_string(),
+                content_encoding: None,
+                headers: self.auth.header().into_iter().collect(),
+            };
+            service::deliver(&self.client, &self.delivery, request).await?;
+            Ok(())
+        })
+    }
+}
+
+impl<C: HttpClient> Sink for OtlpSink<C> {
+    fn write<'a>(&'a self, log: &'a BetterStackLogSchema) -> SinkFuture<'a> {
+        Box::pin(async move {
+            match self.batcher.push(log) {
+                Some(batch) => send_batches(self, batch).await,
+                None => Ok(()),
+            }
+        })
+    }
+
+    fn flush(&self) -> SinkFuture<'_> {
+        Box::pin(send_batches(self, self.batcher.take()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http_client::LogtailError;
+    use crate::r#struct::env_config::{EnvConfig, EnvEnum};
+    use crate::r#struct::log_level::LogLevel;
+    use crate::test_util::{RecordingTransport, ScriptedResponse};
+    use crate::OutputConfig;
+    use crate::{LogSchema, Logger};
+    use serde_json::{json, Value};
+
+    const URL: &str = "http://collector:4318";
+
+    fn sink(
+        transport: &RecordingTransport,
+        protocol: OtlpProtocol,
+    ) -> OtlpSink<RecordingTransport> {
+        OtlpSink::new(transport.clone(), URL)
+            .with_protocol(protocol)
+            .with_delivery(DeliveryConfig {
+                retries: 0,
+                ..DeliveryConfig::default()
+            })
+    }
+
+    fn log(message: &str) -> BetterStackLogSchema {
+        BetterStackLogSchema {
+            service: Some("billing".to_string()),
+            ..BetterStackLogSchema::fixture(LogLevel::Info, message)
+        }
+    }
+
+    #[tokio::test]
+    async fn exports_json_to_the_logs_endpoint() {
+        let transport = RecordingTransport::new();
+        let sink = sink(&transport, OtlpProtocol::HttpJson)
+            .with_auth(HttpAuth::Bearer("token".to_string()))
+            .with_batch(BatchConfig {
+                max_records: 2,
+                ..BatchConfig::default()
+            });
+
+        sink.write(&log("one")).await.unwrap();
+        sink.write(&log("two")).await.unwrap();
+
+        let sent = &transport.requests()[0];
+        assert_eq!(sent.url, "http://collector:4318/v1/logs");
+        assert_eq!(sent.content_type, "application/json");
+        assert_eq!(sent.header("authorization"), Some("Bearer token"));
+        let body: Value = serde_json::from_slice(&sent.body).unwrap();
+        let records = &body["resourceLogs"][0]["scopeLogs"][0]["logRecords"];
+        assert_eq!(records[0]["body"]["stringValue"], "one");
+        assert_eq!(records[1]["body"]["stringValue"], "two");
+        assert_eq!(records[0]["severityText"], "INFO");
+        assert_eq!(
+            body["resourceLogs"][0]["resource"]["attributes"][0],
+            json!({ "key": "service.name", "value": { "stringValue": "billing" } })
+        );
+    }
+
+    #[tokio::test]
+    async fn exports_protobuf() {
+        let transport = RecordingTransport::new();
+
+        sink(&transport, OtlpProtocol::HttpProtobuf)
+            .write(&log("hello"))
+            .await
+            .unwrap();
+
+        let sent = &transport.requests()[0];
+        assert_eq!(sent.content_type, "application/x-protobuf");
+        assert_eq!(sent.body[0], 0x0a);
+        assert!(sent.body.windows(5).any(|window| window == b"hello"));
+        assert!(sent.body.windows(7).any(|window| window == b"billing"));
+    }
+
+    #[tokio::test]
+    async fn surfaces_failures() {
+        let transport = RecordingTransport::new();
+        transport.then_respond(ScriptedResponse::Status(503));
+
+        let result = sink(&transport, OtlpProtocol::HttpProtobuf)
+            .write(&log("hello"))
+            .await;
+
+        assert!(matches!(
+            result,
+            Err(LogtailError::Http { status: 503, .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn logger_exports_records_with_their_timestamp() {
+        let transport = RecordingTransport::new();
+        let config =
+            EnvConfig::from_values("1.0.0".to_string(), EnvEnum::QA, "token".to_string(), false);
+        let logger = Logger::with_client(config, RecordingTransport::new())
+            .with_output(OutputConfig {
+                remote: false,
+                console: false,
+            })
+            .with_sink(sink(&transport, OtlpProtocol::HttpJson), LogLevel::Debug);
+
+        logger
+            .info(LogSchema {
+                message: "exported".to_string(),
+                context: "main.rs:1".to_string(),
+            })
+            .await;
+
+        let body: Value = serde_json::from_slice(&transport.requests()[0].body).unwrap();
+        let record = &body["resourceLogs"][0]["scopeLogs"][0]["logRecords"][0];
+        assert_eq!(record["body"]["stringValue"], "exported");
+        assert_eq!(record["severityNumber"], 9);
+        let time: u64 = record["timeUnixNano"].as_str().unwrap().parse().unwrap();
+        assert!(time > 0);
+    }
+}
@@ -0,0 +1,440 @@
+use crate::http_client::LogtailError;
+use crate::r#struct::betterstack_log_schema::BetterStackLogSchema;
+use crate::r#struct::log_level::LogLevel;
+use serde_json::{json, Map, Value};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Record keys carried by the log record itself rather than its attributes.
+const LIFTED_KEYS: [&str; 6] = [
+    "dt",
+    "level",
+    "message",
+    "trace_id",
+    "span_id",
+    "trace_flags",
+];
+
+/// Record keys already described by the resource attributes.
+const RESOURCE_KEYS: [&str; 4] = ["env", "app_version", "service", "labels"];
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum AnyValue {
+    String(String),
+    Bool(bool),
+    Int(i64),
+    Double(f64),
+    Array(Vec<AnyValue>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct KeyValue {
+    pub(crate) key: String,
+    pub(crate) value: AnyValue,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct LogRecord {
+    pub(crate) time_unix_nano: u64,
+    pub(crate) observed_time_unix_nano: u64,
+    pub(crate) severity_number: i32,
+    pub(crate) severity_text: String,
+    pub(crate) body: Option<AnyValue>,
+    pub(crate) attributes: Vec<KeyValue>,
+    pub(crate) flags: u32,
+    pub(crate) trace_id: Vec<u8>,
+    pub(crate) span_id: Vec<u8>,
+}
+
+/// The records of one resource, under a single instrumentation scope.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ResourceLogs {
+    pub(crate) resource: Vec<KeyValue>,
+    pub(crate) records: Vec<LogRecord>,
+}
+
+/// An `ExportLogsServiceRequest`.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ExportLogsRequest {
+    pub(crate) resource_logs: Vec<ResourceLogs>,
+}
+
+pub(crate) const SCOPE_NAME: &str = env!("CARGO_PKG_NAME");
+pub(crate) const SCOPE_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// OpenTelemetry resource attributes describing the service that logged `log`: its
+/// service name, version, environment and labels.
+pub(crate) fn resource_attributes(log: &BetterStackLogSchema) -> Vec<KeyValue> {
+    let service = log.service.as_deref().unwrap_or("unknown_service");
+    let mut attributes = vec![
+        string_attribute("service.name", service),
+        string_attribute("service.version", &log.app_version),
+        string_attribute("deployment.environment.name", &log.env.to_string()),
+    ];
+    for (key, value) in &log.labels {
+        attributes.push(string_attribute(key, value));
+    }
+    attributes
+}
+
+impl ExportLogsRequest {
+    /// Maps `logs`, grouped by resource in the order the resources first appear.
+    pub(crate) fn from_logs(logs: &[BetterStackLogSchema]) -> Result<Self, LogtailError> {
+        let observed = unix_nanos(SystemTime::now());
+        let mut resource_logs: Vec<ResourceLogs> = Vec::new();
+        for log in logs {
+            let resource = resource_attributes(log);
+            let record = LogRecord::from_log(log, observed)?;
+            match resource_logs
+                .iter_mut()
+                .find(|group| group.resource == resource)
+            {
+                Some(group) => group.records.push(record),
+                None => resource_logs.push(ResourceLogs {
+                    resource,
+                    records: vec![record],
+                }),
+            }
+        }
+        Ok(Self { resource_logs })
+    }
+
+    /// The OTLP/HTTP JSON encoding: camelCase names, 64-bit integers as strings and
+    /// trace ids as hex.
+    pub(crate) fn to_json(&self) -> Value {
+        let resource_logs: Vec<_> = self
+            .resource_logs
+            .iter()
+            .map(|group| {
+                let records: Vec<_> = group.records.iter().map(LogRecord::to_json).collect();
+                json!({
+                    "resource": { "attributes": attributes_json(&group.resource) },
+                    "scopeLogs": [{
+                        "scope": { "name": SCOPE_NAME, "version": SCOPE_VERSION },
+                        "logRecords": records,
+                    }],
+                })
+            })
+            .collect();
+        json!({ "resourceLogs": resource_logs })
+    }
+}
+
+impl LogRecord {
+    fn from_log(
+        log: &BetterStackLogSchema,
+        observed_time_unix_nano: u64,
+    ) -> Result<Self, LogtailError> {
+        let (severity_number, severity_text) = severity(&log.level);
+        let mut attributes = Vec::new();
+        if let Value::Object(fields) = serde_json::to_value(log)? {
+            for (key, value) in &fields {
+                if !LIFTED_KEYS.contains(&key.as_str()) && !RESOURCE_KEYS.contains(&key.as_str()) {
+                    flatten(key, value, &mut attributes);
+                }
+            }
+        }
+        let trace = log.trace.as_ref();
+        Ok(Self {
+            time_unix_nano: unix_nanos(log.dt),
+            observed_time_unix_nano,
+            severity_number,
+            severity_text: severity_text.to_string(),
+            body: Some(AnyValue::String(log.message.clone())),
+            attributes,
+            flags: trace
+                .and_then(|trace| u8::from_str_radix(&trace.trace_flags, 16).ok())
+                .map_or(0, u32::from),
+            trace_id: hex_bytes(trace.map(|trace| trace.trace_id.as_str()), 16),
+            span_id: hex_bytes(trace.map(|trace| trace.span_id.as_str()), 8),
+        })
+    }
+
+    fn to_json(&self) -> Value {
+        let mut record = Map::new();
+        record.insert(
+            "timeUnixNano".into(),
+            self.time_unix_nano.to_string().into(),
+        );
+        record.insert(
+            "observedTimeUnixNano".into(),
+            self.observed_time_unix_nano.to_string().into(),
+        );
+        record.insert("severityNumber".into(), self.severity_number.into());
+        record.insert("severityText".into(), self.severity_text.clone().into());
+        if let Some(body) = &self.body {
+            record.insert("body".into(), body.to_json());
+        }
+        record.insert("attributes".into(), attributes_json(&self.attributes));
+        if self.flags != 0 {
+            record.insert("flags".into(), self.flags.into());
+        }
+        if !self.trace_id.is_empty() {
+            record.insert("traceId".into(), hex(&self.trace_id).into());
+        }
+        if !self.span_id.is_empty() {
+            record.insert("spanId".into(), hex(&self.span_id).into());
+        }
+        Value::Object(record)
+    }
+}
+
+impl AnyValue {
+    /// `None` for `null`, which OTLP has no value for.
+    fn from_json(value: &Value) -> Option<Self> {
+        Some(match value {
+            Value::Null => return None,
+            Value::Bool(b) => AnyValue::Bool(*b),
+            Value::Number(n) => match n.as_i64() {
+                Some(i) => AnyValue::Int(i),
+                None => AnyValue::Double(n.as_f64()?),
+            },
+            Value::String(s) => AnyValue::String(s.clone()),
+            Value::Array(values) => {
+                AnyValue::Array(values.iter().filter_map(AnyValue::from_json).collect())
+            }
+            Value::Object(_) => AnyValue::String(value.to_string()),
+        })
+    }
+
+    fn to_json(&self) -> Value {
+        match self {
+            AnyValue::String(s) => json!({ "stringValue": s }),
+            AnyValue::Bool(b) => json!({ "boolValue": b }),
+            AnyValue::Int(i) => json!({ "intValue": i.to_string() }),
+            AnyValue::Double(d) => json!({ "doubleValue": d }),
+            AnyValue::Array(values) => json!({
+                "arrayValue": { "values": values.iter().map(AnyValue::to_json).collect::<Vec<_>>() }
+            }),
+        }
+    }
+}
+
+/// OpenTelemetry severity number and text of a record level.
+fn severity(level: &LogLevel) -> (i32, &'static str) {
+    match level {
+        LogLevel::Debug => (5, "DEBUG"),
+        LogLevel::Info => (9, "INFO"),
+        LogLevel::Warn => (13, "WARN"),
+        LogLevel::Error => (17, "ERROR"),
+    }
+}
+
+/// Nested objects become dotted keys, e.g. `host.pid`.
+fn flatten(key: &str, value: &Value, attributes: &mut Vec<KeyValue>) {
+    if let Value::Object(fields) = value {
+        for (child, value) in fields {
+            flatten(&format!("{}.{}", key, child), value, attributes);
+        }
+        return;
+    }
+    if let Some(value) = AnyValue::from_json(value) {
+        attributes.push(KeyValue {
+            key: key.to_string(),
+            value,
+        });
+    }
+}
+
+fn string_attribute(key: &str, value: &str) -> KeyValue {
+    KeyValue {
+        key: key.to_string(),
+        value: AnyValue::String(value.to_string()),
+    }
+}
+
+fn attributes_json(attributes: &[KeyValue]) -> Value {
+    attributes
+        .iter()
+        .map(|attribute| json!({ "key": attribute.key, "value": attribute.value.to_json() }))
+        .collect()
+}
+
+fn unix_nanos(time: SystemTime) -> u64 {
+    let nanos = time
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    u64::try_from(nanos).unwrap_or(u64::MAX)
+}
+
+/// Decodes `len` bytes of hex, empty when absent or malformed.
+fn hex_bytes(text: Option<&str>, len: usize) -> Vec<u8> {
+    let Some(text) = text.filter(|text| text.len() == len * 2) else {
+        return Vec::new();
+    };
+    (0..len)
+        .map(|i| u8::from_str_radix(text.get(i * 2..i * 2 + 2)?, 16).ok())
+        .collect::<Option<Vec<_>>>()
+        .unwrap_or_default()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::r#struct::env_config::EnvEnum;
+    use crate::r#struct::metadata_config::HostMetadata;
+    use crate::r#struct::trace_context::TraceContext;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    fn sample_log() -> BetterStackLogSchema {
+        BetterStackLogSchema {
+            dt: UNIX_EPOCH + Duration::from_millis(1_728_030_600_250),
+            context: "billing.rs:42".to_string(),
+            repeat_count: Some(3),
+            host: Some(HostMetadata {
+                pid: Some(7),
+                os: Some("linux".to_string()),
+                ..HostMetadata::default()
+            }),
+            trace: TraceContext::from_traceparent(
+                "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+            ),
+            ..BetterStackLogSchema::fixture(LogLevel::Error, "payment failed")
+        }
+    }
+
+    fn attribute<'a>(attributes: &'a [KeyValue], key: &str) -> Option<&'a AnyValue> {
+        attributes
+            .iter()
+            .find(|attribute| attribute.key == key)
+            .map(|attribute| &attribute.value)
+    }
+
+    #[test]
+    fn resource_describes_service() {
+        let mut log = BetterStackLogSchema {
+            env: EnvEnum::Prod,
+            app_version: "2.0.0".to_string(),
+            service: Some("billing".to_string()),
+            ..sample_log()
+        };
+        log.labels
+            .insert("region".to_string(), "eu-west-1".to_string());
+
+        let resource = resource_attributes(&log);
+
+        let string = |value: &str| Some(AnyValue::String(value.to_string()));
+        assert_eq!(
+            attribute(&resource, "service.name").cloned(),
+            string("billing")
+        );
+        assert_eq!(
+            attribute(&resource, "service.version").cloned(),
+            string("2.0.0")
+        );
+        assert_eq!(
+            attribute(&resource, "deployment.environment.name").cloned(),
+            string("prod")
+        );
+        assert_eq!(attribute(&resource, "region").cloned(), string("eu-west-1"));
+    }
+
+    #[test]
+    fn resource_defaults_service_name() {
+        let resource = resource_attributes(&sample_log());
+
+        assert_eq!(
+            attribute(&resource, "service.name"),
+            Some(&AnyValue::String("unknown_service".to_string()))
+        );
+    }
+
+    #[test]
+    fn maps_record_fields() {
+        let record = LogRecord::from_log(&sample_log(), 1).unwrap();
+
+        assert_eq!(record.time_unix_nano, 1_728_030_600_250_000_000);
+        assert_eq!(record.observed_time_unix_nano, 1);
+        assert_eq!(record.severity_number, 17);
+        assert_eq!(record.severity_text, "ERROR");
+        assert_eq!(
+            record.body,
+            Some(AnyValue::String("payment failed".to_string()))
+        );
+        assert_eq!(record.flags, 1);
+        assert_eq!(record.trace_id.len(), 16);
+        assert_eq!(
+            record.span_id,
+            vec![0x00, 0xf0, 0x67, 0xaa, 0x0b, 0xa9, 0x02, 0xb7]
+        );
+    }
+
+    #[test]
+    fn maps_remaining_fields_to_flat_attributes() {
+        let record = LogRecord::from_log(&sample_log(), 1).unwrap();
+
+        let keys: Vec<_> = record.attributes.iter().map(|a| a.key.as_str()).collect();
+        assert_eq!(keys, vec!["context", "host.os", "host.pid", "repeat_count"]);
+        assert_eq!(
+            attribute(&record.attributes, "host.pid"),
+            Some(&AnyValue::Int(7))
+        );
+    }
+
+    #[test]
+    fn maps_severity_of_every_level() {
+        assert_eq!(severity(&LogLevel::Debug), (5, "DEBUG"));
+        assert_eq!(severity(&LogLevel::Info), (9, "INFO"));
+        assert_eq!(severity(&LogLevel::Warn), (13, "WARN"));
+        assert_eq!(severity(&LogLevel::Error), (17, "ERROR"));
+    }
+
+    #[test]
+    fn groups_records_by_resource() {
+        let other = BetterStackLogSchema {
+            service: Some("billing".to_string()),
+            ..sample_log()
+        };
+
+        let request = ExportLogsRequest::from_logs(&[sample_log(), other, sample_log()]).unwrap();
+
+        let counts: Vec<_> = request
+            .resource_logs
+            .iter()
+            .map(|group| group.records.len())
+            .collect();
+        assert_eq!(counts, vec![2, 1]);
+    }
+
+    #[test]
+    fn json_follows_otlp_mapping() {
+        let log = BetterStackLogSchema {
+            service: Some("billing".to_string()),
+            ..sample_log()
+        };
+
+        let value = ExportLogsRequest::from_logs(&[log]).unwrap().to_json();
+
+        let resource_logs = &value["resourceLogs"][0];
+        assert_eq!(
+            resource_logs["resource"]["attributes"][0],
+            json!({ "key": "service.name", "value": { "stringValue": "billing" } })
+        );
+        assert_eq!(resource_logs["scopeLogs"][0]["scope"]["name"], SCOPE_NAME);
+        let record = &resource_logs["scopeLogs"][0]["logRecords"][0];
+        assert_eq!(record["timeUnixNano"], "1728030600250000000");
+        assert_eq!(record["severityNumber"], 17);
+        assert_eq!(record["body"], json!({ "stringValue": "payment failed" }));
+        assert_eq!(record["traceId"], "4bf92f3577b34da6a3ce929d0e0e4736");
+        assert_eq!(record["spanId"], "00f067aa0ba902b7");
+        assert_eq!(record["flags"], 1);
+    }
+
+    #[test]
+    fn json_omits_missing_trace() {
+        let log = BetterStackLogSchema {
+            trace: None,
+            ..sample_log()
+        };
+
+        let value = ExportLogsRequest::from_logs(&[log]).unwrap().to_json();
+
+        let record = &value["resourceLogs"][0]["scopeLogs"][0]["logRecords"][0];
+        assert!(record.get("traceId").is_none());
+        assert!(record.get("flags").is_none());
+    }
+}
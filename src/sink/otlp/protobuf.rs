@@ -0,0 +1,254 @@
+//! Hand-rolled protobuf encoding of the few OTLP messages this crate sends, field numbers
+//! from `opentelemetry/proto/{collector/logs/v1,logs/v1,common/v1,resource/v1}`.
+
+use super::model::{
+    AnyValue, ExportLogsRequest, KeyValue, LogRecord, ResourceLogs, SCOPE_NAME, SCOPE_VERSION,
+};
+
+const VARINT: u32 = 0;
+const FIXED64: u32 = 1;
+const LEN: u32 = 2;
+const FIXED32: u32 = 5;
+
+#[derive(Default)]
+struct Writer(Vec<u8>);
+
+impl Writer {
+    fn varint(&mut self, mut value: u64) {
+        while value >= 0x80 {
+            self.0.push((value as u8) | 0x80);
+            value >>= 7;
+        }
+        self.0.push(value as u8);
+    }
+
+    fn tag(&mut self, field: u32, wire_type: u32) {
+        self.varint(u64::from(field << 3 | wire_type));
+    }
+
+    fn uint(&mut self, field: u32, value: u64) {
+        self.tag(field, VARINT);
+        self.varint(value);
+    }
+
+    fn fixed64(&mut self, field: u32, value: u64) {
+        self.tag(field, FIXED64);
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn fixed32(&mut self, field: u32, value: u32) {
+        self.tag(field, FIXED32);
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn bytes(&mut self, field: u32, value: &[u8]) {
+        self.tag(field, LEN);
+        self.varint(value.len() as u64);
+        self.0.extend_from_slice(value);
+    }
+
+    fn string(&mut self, field: u32, value: &str) {
+        self.bytes(field, value.as_bytes());
+    }
+
+    fn message(&mut self, field: u32, encode: impl FnOnce(&mut Writer)) {
+        let mut nested = Writer::default();
+        encode(&mut nested);
+        self.bytes(field, &nested.0);
+    }
+}
+
+/// Encodes an `ExportLogsServiceRequest`.
+pub(crate) fn encode(request: &ExportLogsRequest) -> Vec<u8> {
+    let mut writer = Writer::default();
+    // ExportLogsServiceRequest.resource_logs
+    for group in &request.resource_logs {
+        writer.message(1, |writer| resource_logs(writer, group));
+    }
+    writer.0
+}
+
+fn resource_logs(writer: &mut Writer, group: &ResourceLogs) {
+    // ResourceLogs.resource
+    writer.message(1, |resource| key_values(resource, 1, &group.resource));
+    // ResourceLogs.scope_logs
+    writer.message(2, |scope_logs| {
+        // ScopeLogs.scope
+        scope_logs.message(1, |scope| {
+            scope.string(1, SCOPE_NAME);
+            scope.string(2, SCOPE_VERSION);
+        });
+        // ScopeLogs.log_records
+        for record in &group.records {
+            scope_logs.message(2, |writer| log_record(writer, record));
+        }
+    });
+}
+
+fn log_record(writer: &mut Writer, record: &LogRecord) {
+    writer.fixed64(1, record.time_unix_nano);
+    writer.fixed64(11, record.observed_time_unix_nano);
+    if record.severity_number != 0 {
+        writer.uint(2, record.severity_number as u64);
+    }
+    if !record.severity_text.is_empty() {
+        writer.string(3, &record.severity_text);
+    }
+    if let Some(body) = &record.body {
+        writer.message(5, |writer| any_value(writer, body));
+    }
+    key_values(writer, 6, &record.attributes);
+    if record.flags != 0 {
+        writer.fixed32(8, record.flags);
+    }
+    if !record.trace_id.is_empty() {
+        writer.bytes(9, &record.trace_id);
+    }
+    if !record.span_id.is_empty() {
+        writer.bytes(10, &record.span_id);
+    }
+}
+
+fn key_values(writer: &mut Writer, field: u32, attributes: &[KeyValue]) {
+    for attribute in attributes {
+        writer.message(field, |writer| {
+            writer.string(1, &attribute.key);
+            writer.message(2, |writer| any_value(writer, &attribute.value));
+        });
+    }
+}
+
+fn any_value(writer: &mut Writer, value: &AnyValue) {
+    match value {
+        AnyValue::String(s) => writer.string(1, s),
+        AnyValue::Bool(b) => writer.uint(2, u64::from(*b)),
+        AnyValue::Int(i) => writer.uint(3, *i as u64),
+        AnyValue::Double(d) => writer.fixed64(4, d.to_bits()),
+        AnyValue::Array(values) => writer.message(5, |array| {
+            for value in values {
+                array.message(1, |writer| any_value(writer, value));
+            }
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encoded(value: &AnyValue) -> Vec<u8> {
+        let mut writer = Writer::default();
+        any_value(&mut writer, value);
+        writer.0
+    }
+
+    #[test]
+    fn varints_use_seven_bit_groups() {
+        let mut writer = Writer::default();
+        writer.varint(1);
+        writer.varint(300);
+        assert_eq!(writer.0, vec![0x01, 0xac, 0x02]);
+    }
+
+    #[test]
+    fn negative_ints_take_ten_bytes() {
+        assert_eq!(
+            encoded(&AnyValue::Int(-1)),
+            vec![0x18, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01]
+        );
+    }
+
+    #[test]
+    fn encodes_any_values() {
+        assert_eq!(
+            encoded(&AnyValue::String("hi".to_string())),
+            vec![0x0a, 0x02, b'h', b'i']
+        );
+        assert_eq!(encoded(&AnyValue::Bool(true)), vec![0x10, 0x01]);
+        assert_eq!(encoded(&AnyValue::Int(5)), vec![0x18, 0x05]);
+        assert_eq!(
+            encoded(&AnyValue::Array(vec![AnyValue::Int(1)])),
+            vec![0x2a, 0x04, 0x0a, 0x02, 0x18, 0x01]
+        );
+    }
+
+    #[test]
+    fn encodes_key_values() {
+        let mut writer = Writer::default();
+        let attribute = KeyValue {
+            key: "a".to_string(),
+            value: AnyValue::String("b".to_string()),
+        };
+
+        key_values(&mut writer, 1, &[attribute]);
+
+        assert_eq!(
+            writer.0,
+            vec![0x0a, 0x08, 0x0a, 0x01, b'a', 0x12, 0x03, 0x0a, 0x01, b'b']
+        );
+    }
+
+    #[test]
+    fn encodes_log_record_fields() {
+        let record = LogRecord {
+            time_unix_nano: 1,
+            observed_time_unix_nano: 2,
+            severity_number: 9,
+            severity_text: "INFO".to_string(),
+            body: None,
+            attributes: Vec::new(),
+            flags: 1,
+            trace_id: Vec::new(),
+            span_id: vec![0xab; 8],
+        };
+        let mut writer = Writer::default();
+
+        log_record(&mut writer, &record);
+
+        let mut expected = vec![0x09, 1, 0, 0, 0, 0, 0, 0, 0];
+        expected.extend([0x59, 2, 0, 0, 0, 0, 0, 0, 0]);
+        expected.extend([0x10, 0x09]);
+        expected.extend([0x1a, 0x04, b'I', b'N', b'F', b'O']);
+        expected.extend([0x45, 1, 0, 0, 0]);
+        expected.extend([0x52, 0x08]);
+        expected.extend([0xab; 8]);
+        assert_eq!(writer.0, expected);
+    }
+
+    #[test]
+    fn request_nests_resource_and_scope_logs() {
+        let request = ExportLogsRequest {
+            resource_logs: vec![ResourceLogs {
+                resource: Vec::new(),
+                records: Vec::new(),
+            }],
+        };
+
+        let bytes = encode(&request);
+
+        // resource_logs { resource {} scope_logs { scope { name version } } }
+        assert_eq!(bytes[0], 0x0a);
+        assert_eq!(&bytes[2..4], &[0x0a, 0x00]);
+        assert_eq!(bytes[4], 0x12);
+        assert!(bytes
+            .windows(SCOPE_NAME.len())
+            .any(|window| window == SCOPE_NAME.as_bytes()));
+    }
+
+    #[test]
+    fn request_repeats_resource_logs_per_resource() {
+        let group = ResourceLogs {
+            resource: Vec::new(),
+            records: Vec::new(),
+        };
+        let request = ExportLogsRequest {
+            resource_logs: vec![group.clone(), group],
+        };
+
+        let bytes = encode(&request);
+
+        let half = bytes.len() / 2;
+        assert_eq!(bytes[0], 0x0a);
+        assert_eq!(bytes[..half], bytes[half..]);
+    }
+}
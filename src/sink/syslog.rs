@@ -0,0 +1,386 @@
+use super::{Sink, SinkFuture};
+use crate::http_client::LogtailError;
+use crate::r#struct::betterstack_log_schema::BetterStackLogSchema;
+use crate::r#struct::log_level::LogLevel;
+use crate::time::format_rfc3339;
+use std::io;
+#[cfg(unix)]
+use std::path::PathBuf;
+use tokio::io::AsyncWriteExt;
+#[cfg(unix)]
+use tokio::net::UnixDatagram;
+use tokio::net::{TcpStream, UdpSocket};
+use tokio::sync::Mutex;
+
+/// SD-ID of the structured data element, under the example enterprise number of RFC 5612.
+const SD_ID: &str = "logtail@32473";
+
+/// Where `SyslogSink` delivers its messages.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SyslogTransport {
+    /// One datagram per message to `host:port`, e.g. `127.0.0.1:514`.
+    Udp(String),
+    /// A stream to `host:port`, messages framed by octet counting as in RFC 6587.
+    Tcp(String),
+    /// One datagram per message to a local socket such as `/dev/log`.
+    #[cfg(unix)]
+    Unix(PathBuf),
+}
+
+/// Facility the priority of every message is computed from.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Facility {
+    #[default]
+    User = 1,
+    Daemon = 3,
+    Local0 = 16,
+    Local1 = 17,
+    Local2 = 18,
+    Local3 = 19,
+    Local4 = 20,
+    Local5 = 21,
+    Local6 = 22,
+    Local7 = 23,
+}
+
+/// Sends records as RFC 5424 syslog messages.
+///
+/// `env`, `app_version` and `context` travel as structured data, along with the trace
+/// and repeat count when present. The host name and process id come from the record's
+/// host metadata and the application name from its service, `-` when missing. The
+/// connection is opened on the first write and reopened after a failed one.
+pub struct SyslogSink {
+    transport: SyslogTransport,
+    facility: Facility,
+    app_name: Option<String>,
+    connection: Mutex<Option<Connection>>,
+}
+
+enum Connection {
+    Udp(UdpSocket),
+    Tcp(TcpStream),
+    #[cfg(unix)]
+    Unix(UnixDatagram),
+}
+
+impl SyslogSink {
+    pub fn new(transport: SyslogTransport) -> Self {
+        Self {
+            transport,
+            facility: Facility::default(),
+            app_name: None,
+            connection: Mutex::new(None),
+        }
+    }
+
+    pub fn with_facility(mut self, facility: Facility) -> Self {
+        self.facility = facility;
+        self
+    }
+
+    /// APP-NAME of every message instead of the record's service.
+    pub fn with_app_name(mut self, app_name: impl Into<String>) -> Self {
+        self.app_name = Some(app_name.into());
+        self
+    }
+
+    async fn send(&self, log: &BetterStackLogSchema) -> Result<(), LogtailError> {
+        let message = format_message(log, self.facility, self.app_name.as_deref());
+        let mut connection = self.connection.lock().await;
+        if connection.is_none() {
+            *connection = Some(connect(&self.transport).await?);
+        }
+        let result = match connection.as_mut().unwrap() {
+            Connection::Udp(socket) => socket.send(message.as_bytes()).await.map(drop),
+            Connection::Tcp(stream) => {
+                let frame = format!("{} {}", message.len(), message);
+                stream.write_all(frame.as_bytes()).await
+            }
+            #[cfg(unix)]
+            Connection::Unix(socket) => socket.send(message.as_bytes()).await.map(drop),
+        };
+        if result.is_err() {
+            *connection = None;
+        }
+        Ok(result?)
+    }
+}
+
+impl Sink for SyslogSink {
+    fn write<'a>(&'a self, log: &'a BetterStackLogSchema) -> SinkFuture<'a> {
+        Box::pin(self.send(log))
+    }
+}
+
+async fn connect(transport: &SyslogTransport) -> io::Result<Connection> {
+    match transport {
+        SyslogTransport::Udp(address) => {
+            let target = tokio::net::lookup_host(address.as_str())
+                .await?
+                .next()
+                .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, address.clone()))?;
+            let local = if target.is_ipv4() {
+                "0.0.0.0:0"
+            } else {
+                "[::]:0"
+            };
+            let socket = UdpSocket::bind(local).await?;
+            socket.connect(target).await?;
+            Ok(Connection::Udp(socket))
+        }
+        SyslogTransport::Tcp(address) => {
+            Ok(Connection::Tcp(TcpStream::connect(address.as_str()).await?))
+        }
+        #[cfg(unix)]
+        SyslogTransport::Unix(path) => {
+            let socket = UnixDatagram::unbound()?;
+            socket.connect(path)?;
+            Ok(Connection::Unix(socket))
+        }
+    }
+}
+
+/// `<PRI>1 TIMESTAMP HOSTNAME APP-NAME PROCID MSGID [SD] BOM MSG`
+fn format_message(
+    log: &BetterStackLogSchema,
+    facility: Facility,
+    app_name: Option<&str>,
+) -> String {
+    let priority = facility as u8 * 8 + severity(&log.level);
+    let host = log.host.as_ref();
+    let hostname = host.and_then(|host| host.hostname.as_deref());
+    let app_name = app_name.or(log.service.as_deref());
+    let proc_id = host.and_then(|host| host.pid).map(|pid| pid.to_string());
+
+    format!(
+        "<{}>1 {} {} {} {} - {} \u{feff}{}",
+        priority,
+        format_rfc3339(log.dt),
+        header_field(hostname, 255),
+        header_field(app_name, 48),
+        header_field(proc_id.as_deref(), 128),
+        structured_data(log),
+        log.message
+    )
+}
+
+fn severity(level: &LogLevel) -> u8 {
+    match level {
+        LogLevel::Debug => 7,
+        LogLevel::Info => 6,
+        LogLevel::Warn => 4,
+        LogLevel::Error => 3,
+    }
+}
+
+/// Printable ASCII without spaces, at most `max` characters, or the `-` nil value.
+fn header_field(value: Option<&str>, max: usize) -> String {
+    let value: String = value
+        .unwrap_or_default()
+        .chars()
+        .map(|c| if c.is_ascii_graphic() { c } else { '_' })
+        .take(max)
+        .collect();
+    if value.is_empty() {
+        "-".to_string()
+    } else {
+        value
+    }
+}
+
+fn structured_data(log: &BetterStackLogSchema) -> String {
+    let mut params = vec![
+        ("env", log.env.to_string()),
+        ("app_version", log.app_version.clone()),
+        ("context", log.context.clone()),
+    ];
+    if let Some(count) = log.repeat_count {
+        params.push(("repeat_count", count.to_string()));
+    }
+    if let Some(trace) = &log.trace {
+        params.push(("trace_id", trace.trace_id.clone()));
+        params.push(("span_id", trace.span_id.clone()));
+    }
+
+    let mut element = format!("[{}", SD_ID);
+    for (name, value) in params {
+        element.push_str(&format!(" {}=\"{}\"", name, escape_param(&value)));
+    }
+    element.push(']');
+    element
+}
+
+/// Escapes `"`, `\` and `]`, the characters PARAM-VALUE may not hold as they are.
+fn escape_param(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '"' | '\\' | ']') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::r#struct::env_config::{EnvConfig, EnvEnum};
+    use crate::r#struct::metadata_config::HostMetadata;
+    use crate::r#struct::trace_context::TraceContext;
+    use std::time::{Duration, UNIX_EPOCH};
+    use tokio::io::AsyncReadExt;
+    use tokio::net::TcpListener;
+
+    fn test_log(level: LogLevel, message: &str) -> BetterStackLogSchema {
+        let config =
+            EnvConfig::from_values("1.0.0".to_string(), EnvEnum::QA, "token".to_string(), false);
+        let mut log =
+            BetterStackLogSchema::new(&config, level, message.to_string(), "main.rs:1".to_string());
+        log.dt = UNIX_EPOCH + Duration::from_millis(1_728_030_600_250);
+        log
+    }
+
+    #[test]
+    fn formats_rfc5424_message() {
+        let mut log = test_log(LogLevel::Warn, "disk almost full");
+        log.service = Some("billing".to_string());
+        log.host = Some(HostMetadata {
+            hostname: Some("web-1".to_string()),
+            pid: Some(42),
+            ..HostMetadata::default()
+        });
+
+        let message = format_message(&log, Facility::Local0, None);
+
+        assert_eq!(
+            message,
+            "<132>1 2024-10-04T08:30:00.250Z web-1 billing 42 - \
+             [logtail@32473 env=\"qa\" app_version=\"1.0.0\" context=\"main.rs:1\"] \
+             \u{feff}disk almost full"
+        );
+    }
+
+    #[test]
+    fn missing_header_fields_are_nil() {
+        let message = format_message(&test_log(LogLevel::Info, "hi"), Facility::User, None);
+
+        assert!(message.starts_with("<14>1 2024-10-04T08:30:00.250Z - - - - [logtail@32473 "));
+    }
+
+    #[test]
+    fn app_name_overrides_service() {
+        let mut log = test_log(LogLevel::Info, "hi");
+        log.service = Some("billing".to_string());
+
+        let message = format_message(&log, Facility::User, Some("my app"));
+
+        assert!(message.contains(" - my_app - - ["));
+    }
+
+    #[test]
+    fn maps_levels_to_severities() {
+        let priority =
+            |level| format_message(&test_log(level, "hi"), Facility::User, None)[..4].to_string();
+
+        assert_eq!(priority(LogLevel::Debug), "<15>");
+        assert_eq!(priority(LogLevel::Info), "<14>");
+        assert_eq!(priority(LogLevel::Warn), "<12>");
+        assert_eq!(priority(LogLevel::Error), "<11>");
+    }
+
+    #[test]
+    fn structured_data_is_escaped_and_carries_trace() {
+        let mut log = test_log(LogLevel::Info, "hi");
+        log.context = r#"say "hi" [x]\y"#.to_string();
+        log.repeat_count = Some(3);
+        log.trace = TraceContext::from_traceparent(
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+        );
+
+        let data = structured_data(&log);
+
+        assert_eq!(
+            data,
+            "[logtail@32473 env=\"qa\" app_version=\"1.0.0\" \
+             context=\"say \\\"hi\\\" [x\\]\\\\y\" repeat_count=\"3\" \
+             trace_id=\"4bf92f3577b34da6a3ce929d0e0e4736\" span_id=\"00f067aa0ba902b7\"]"
+        );
+    }
+
+    #[tokio::test]
+    async fn sends_udp_datagrams() {
+        let listener = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let sink = SyslogSink::new(SyslogTransport::Udp(address));
+
+        sink.write(&test_log(LogLevel::Error, "one")).await.unwrap();
+        sink.write(&test_log(LogLevel::Info, "two")).await.unwrap();
+
+        let mut buffer = [0; 1024];
+        let len = listener.recv(&mut buffer).await.unwrap();
+        let first = String::from_utf8_lossy(&buffer[..len]).into_owned();
+        let len = listener.recv(&mut buffer).await.unwrap();
+        let second = String::from_utf8_lossy(&buffer[..len]).into_owned();
+        assert!(first.starts_with("<11>1 ") && first.ends_with("\u{feff}one"));
+        assert!(second.starts_with("<14>1 ") && second.ends_with("\u{feff}two"));
+    }
+
+    #[tokio::test]
+    async fn frames_tcp_messages_by_octet_count() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let sink = SyslogSink::new(SyslogTransport::Tcp(address));
+        let log = test_log(LogLevel::Info, "héllo");
+
+        sink.write(&log).await.unwrap();
+        sink.write(&log).await.unwrap();
+        drop(sink);
+
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let mut received = Vec::new();
+        stream.read_to_end(&mut received).await.unwrap();
+        let message = format_message(&log, Facility::User, None);
+        let frame = format!("{} {}", message.len(), message);
+        assert_eq!(String::from_utf8(received).unwrap(), frame.repeat(2));
+    }
+
+    #[tokio::test]
+    async fn reconnects_after_failed_write() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        drop(listener);
+        let sink = SyslogSink::new(SyslogTransport::Tcp(address.clone()));
+
+        let result = sink.write(&test_log(LogLevel::Info, "lost")).await;
+        assert!(matches!(result, Err(LogtailError::Io(_))));
+
+        let listener = TcpListener::bind(&address).await.unwrap();
+        sink.write(&test_log(LogLevel::Info, "found"))
+            .await
+            .unwrap();
+        drop(sink);
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let mut received = String::new();
+        stream.read_to_string(&mut received).await.unwrap();
+        assert!(received.ends_with("\u{feff}found"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn sends_unix_datagrams() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("log.sock");
+        let listener = UnixDatagram::bind(&path).unwrap();
+        let sink = SyslogSink::new(SyslogTransport::Unix(path)).with_facility(Facility::Daemon);
+
+        sink.write(&test_log(LogLevel::Debug, "local"))
+            .await
+            .unwrap();
+
+        let mut buffer = [0; 1024];
+        let len = listener.recv(&mut buffer).await.unwrap();
+        let message = String::from_utf8_lossy(&buffer[..len]);
+        assert!(message.starts_with("<31>1 ") && message.ends_with("\u{feff}local"));
+    }
+}
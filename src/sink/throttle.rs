@@ -0,0 +1,345 @@
+use crate::r#struct::betterstack_log_schema::BetterStackLogSchema;
+use crate::r#struct::delivery_config::DeliveryConfig;
+use crate::r#struct::env_config::EnvConfig;
+use crate::r#struct::log_level::LogLevel;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Token bucket bounding how many records are shipped.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RateLimitConfig {
+    /// Records shipped per second once the burst is used up.
+    pub per_second: u32,
+    /// Records that can be shipped at once after a quiet period.
+    pub burst: u32,
+}
+
+/// Share of records shipped per level, from `0.0` (none) to `1.0` (all).
+#[derive(Debug, Clone, PartialEq)]
+pub struct SamplingConfig {
+    pub debug: f64,
+    pub info: f64,
+    pub warn: f64,
+    pub error: f64,
+}
+
+impl Default for SamplingConfig {
+    /// Ships everything.
+    fn default() -> Self {
+        Self {
+            debug: 1.0,
+            info: 1.0,
+            warn: 1.0,
+            error: 1.0,
+        }
+    }
+}
+
+impl SamplingConfig {
+    pub fn rate(&self, level: &LogLevel) -> f64 {
+        match level {
+            LogLevel::Debug => self.debug,
+            LogLevel::Info => self.info,
+            LogLevel::Warn => self.warn,
+            LogLevel::Error => self.error,
+        }
+    }
+}
+
+struct Bucket {
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+#[derive(Default, Clone, Copy)]
+struct Suppressed {
+    rate_limited: u64,
+    sampled_out: u64,
+}
+
+struct SuppressedState {
+    counts: Suppressed,
+    since: Instant,
+    /// A summary is being shipped, so no other is taken meanwhile.
+    pending: bool,
+}
+
+/// A summary record taken from a [`Throttle`]. Its counts are only reset once
+/// [`Summary::sent`] is called, so a summary that failed to ship is retried later.
+pub(crate) struct Summary<'a> {
+    pub(crate) record: BetterStackLogSchema,
+    throttle: &'a Throttle,
+    counts: Suppressed,
+}
+
+impl Summary<'_> {
+    pub(crate) fn sent(self) {
+        let mut state = self.throttle.suppressed.lock().unwrap();
+        state.counts.rate_limited -= self.counts.rate_limited;
+        state.counts.sampled_out -= self.counts.sampled_out;
+        state.since = Instant::now();
+    }
+}
+
+impl Drop for Summary<'_> {
+    fn drop(&mut self) {
+        self.throttle.suppressed.lock().unwrap().pending = false;
+    }
+}
+
+/// Applies `DeliveryConfig::sampling` then `DeliveryConfig::rate_limit`, counting what it drops.
+pub(crate) struct Throttle {
+    rate_limit: Option<RateLimitConfig>,
+    sampling: Option<SamplingConfig>,
+    summary_interval: Duration,
+    bucket: Mutex<Bucket>,
+    suppressed: Mutex<SuppressedState>,
+}
+
+impl Throttle {
+    /// `None` unless the config samples or rate-limits.
+    pub(crate) fn from_config(config: &DeliveryConfig) -> Option<Self> {
+        if config.rate_limit.is_none() && config.sampling.is_none() {
+            return None;
+        }
+        let burst = config.rate_limit.as_ref().map_or(0, |limit| limit.burst);
+        Some(Self {
+            rate_limit: config.rate_limit.clone(),
+            sampling: config.sampling.clone(),
+            summary_interval: config.summary_interval,
+            bucket: Mutex::new(Bucket {
+                tokens: f64::from(burst),
+                refilled_at: Instant::now(),
+            }),
+            suppressed: Mutex::new(SuppressedState {
+                counts: Suppressed::default(),
+                since: Instant::now(),
+                pending: false,
+            }),
+        })
+    }
+
+    /// Whether a record at `level` is shipped.
+    pub(crate) fn admit(&self, level: &LogLevel) -> bool {
+        if let Some(sampling) = &self.sampling {
+            if fastrand::f64() >= sampling.rate(level) {
+                self.suppressed.lock().unwrap().counts.sampled_out += 1;
+                return false;
+            }
+        }
+        if let Some(limit) = &self.rate_limit {
+            if !self.take_token(limit) {
+                self.suppressed.lock().unwrap().counts.rate_limited += 1;
+                return false;
+            }
+        }
+        true
+    }
+
+    fn take_token(&self, limit: &RateLimitConfig) -> bool {
+        let mut bucket = self.bucket.lock().unwrap();
+        let now = Instant::now();
+        let elapsed = now.duration_since(bucket.refilled_at).as_secs_f64();
+        bucket.tokens =
+            (bucket.tokens + elapsed * f64::from(limit.per_second)).min(f64::from(limit.burst));
+        bucket.refilled_at = now;
+        if bucket.tokens < 1.0 {
+            return false;
+        }
+        bucket.tokens -= 1.0;
+        true
+    }
+
+    /// A `Warn` record counting what was dropped, once per `summary_interval` (or right away
+    /// when `force`d) and only if something was. Checked on every write and at flush rather
+    /// than on a timer, so a quiet logger reports on its next record or `Logger::flush`.
+    pub(crate) fn take_summary(&self, env_config: &EnvConfig, force: bool) -> Option<Summary<'_>> {
+        let mut state = self.suppressed.lock().unwrap();
+        let elapsed = state.since.elapsed();
+        if state.pending || (!force && elapsed < self.summary_interval) {
+            return None;
+        }
+        let counts = state.counts;
+        let total = counts.rate_limited + counts.sampled_out;
+        if total == 0 {
+            return None;
+        }
+        state.pending = true;
+        drop(state);
+        let message = format!(
+            "suppressed {} logs in the last {}s: {} rate limited, {} sampled out",
+            total,
+            elapsed.as_secs(),
+            counts.rate_limited,
+            counts.sampled_out
+        );
+        Some(Summary {
+            record: BetterStackLogSchema::new(
+                env_config,
+                LogLevel::Warn,
+                message,
+                env!("CARGO_PKG_NAME").to_string(),
+            ),
+            throttle: self,
+            counts,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::r#struct::env_config::EnvEnum;
+
+    fn throttle(
+        rate_limit: Option<RateLimitConfig>,
+        sampling: Option<SamplingConfig>,
+        summary_interval: Duration,
+    ) -> Throttle {
+        Throttle::from_config(&DeliveryConfig {
+            rate_limit,
+            sampling,
+            summary_interval,
+            ..DeliveryConfig::default()
+        })
+        .unwrap()
+    }
+
+    fn test_config() -> EnvConfig {
+        EnvConfig::from_values("1.0.0".to_string(), EnvEnum::QA, "token".to_string(), false)
+    }
+
+    #[test]
+    fn disabled_without_limit_or_sampling() {
+        assert!(Throttle::from_config(&DeliveryConfig::default()).is_none());
+    }
+
+    #[test]
+    fn rate_limit_allows_burst_then_blocks() {
+        let limit = RateLimitConfig {
+            per_second: 1,
+            burst: 3,
+        };
+        let throttle = throttle(Some(limit), None, Duration::from_secs(60));
+
+        let admitted = (0..5).filter(|_| throttle.admit(&LogLevel::Info)).count();
+
+        assert_eq!(admitted, 3);
+    }
+
+    #[test]
+    fn rate_limit_refills_over_time() {
+        let limit = RateLimitConfig {
+            per_second: 100,
+            burst: 1,
+        };
+        let throttle = throttle(Some(limit), None, Duration::from_secs(60));
+        assert!(throttle.admit(&LogLevel::Info));
+        assert!(!throttle.admit(&LogLevel::Info));
+
+        std::thread::sleep(Duration::from_millis(20));
+
+        assert!(throttle.admit(&LogLevel::Info));
+    }
+
+    #[test]
+    fn sampling_is_level_aware() {
+        let sampling = SamplingConfig {
+            info: 0.0,
+            ..SamplingConfig::default()
+        };
+        let throttle = throttle(None, Some(sampling), Duration::from_secs(60));
+
+        assert!(!throttle.admit(&LogLevel::Info));
+        assert!(throttle.admit(&LogLevel::Error));
+    }
+
+    #[test]
+    fn sampling_ships_roughly_the_configured_share() {
+        let sampling = SamplingConfig {
+            info: 0.1,
+            ..SamplingConfig::default()
+        };
+        let throttle = throttle(None, Some(sampling), Duration::from_secs(60));
+
+        let admitted = (0..10_000)
+            .filter(|_| throttle.admit(&LogLevel::Info))
+            .count();
+
+        assert!((700..1300).contains(&admitted), "admitted {}", admitted);
+    }
+
+    #[test]
+    fn summary_counts_suppressed_records() {
+        let limit = RateLimitConfig {
+            per_second: 0,
+            burst: 1,
+        };
+        let sampling = SamplingConfig {
+            debug: 0.0,
+            ..SamplingConfig::default()
+        };
+        let throttle = throttle(Some(limit), Some(sampling), Duration::ZERO);
+        throttle.admit(&LogLevel::Info);
+        throttle.admit(&LogLevel::Info);
+        throttle.admit(&LogLevel::Info);
+        throttle.admit(&LogLevel::Debug);
+
+        let summary = throttle.take_summary(&test_config(), false).unwrap();
+
+        assert_eq!(summary.record.level, LogLevel::Warn);
+        assert!(summary
+            .record
+            .message
+            .starts_with("suppressed 3 logs in the last 0s: 2 rate limited, 1 sampled out"));
+        assert!(throttle.take_summary(&test_config(), false).is_none());
+        summary.sent();
+        assert!(throttle.take_summary(&test_config(), false).is_none());
+    }
+
+    #[test]
+    fn unsent_summary_keeps_counts() {
+        let sampling = SamplingConfig {
+            info: 0.0,
+            ..SamplingConfig::default()
+        };
+        let throttle = throttle(None, Some(sampling), Duration::ZERO);
+        throttle.admit(&LogLevel::Info);
+        drop(throttle.take_summary(&test_config(), false));
+
+        throttle.admit(&LogLevel::Info);
+        let summary = throttle.take_summary(&test_config(), false).unwrap();
+
+        assert!(summary.record.message.starts_with("suppressed 2 logs"));
+    }
+
+    #[test]
+    fn sent_summary_keeps_later_counts() {
+        let sampling = SamplingConfig {
+            info: 0.0,
+            ..SamplingConfig::default()
+        };
+        let throttle = throttle(None, Some(sampling), Duration::ZERO);
+        throttle.admit(&LogLevel::Info);
+        let summary = throttle.take_summary(&test_config(), false).unwrap();
+
+        throttle.admit(&LogLevel::Info);
+        summary.sent();
+
+        let summary = throttle.take_summary(&test_config(), false).unwrap();
+        assert!(summary.record.message.starts_with("suppressed 1 logs"));
+    }
+
+    #[test]
+    fn summary_waits_for_interval() {
+        let sampling = SamplingConfig {
+            info: 0.0,
+            ..SamplingConfig::default()
+        };
+        let throttle = throttle(None, Some(sampling), Duration::from_secs(60));
+        throttle.admit(&LogLevel::Info);
+
+        assert!(throttle.take_summary(&test_config(), false).is_none());
+        assert!(throttle.take_summary(&test_config(), true).is_some());
+    }
+}
@@ -1,16 +1,44 @@
-use serde::Serialize;
+use serde::{Serialize, Serializer};
+use std::collections::BTreeMap;
 use std::fmt::{Display, Formatter};
+use std::time::SystemTime;
 
 use crate::r#struct::env_config::{EnvConfig, EnvEnum};
+use crate::r#struct::error_details::ErrorDetails;
 use crate::r#struct::log_level::LogLevel;
+use crate::r#struct::metadata_config::{HostMetadata, ThreadMetadata};
+use crate::r#struct::trace_context::TraceContext;
+use crate::time::format_rfc3339;
 
 #[derive(Debug, Serialize, Clone)]
 pub struct BetterStackLogSchema {
+    /// When the record was created, serialized as the RFC 3339 `dt` BetterStack expects.
+    #[serde(serialize_with = "serialize_dt")]
+    pub dt: SystemTime,
     pub env: EnvEnum,
     pub message: String,
     pub context: String,
     pub level: LogLevel,
     pub app_version: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub service: Option<String>,
+    /// `EnvConfig::labels`, the same on every record.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub labels: BTreeMap<String, String>,
+    /// How many identical records this one stands for, set by the logger's dedup stage.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub repeat_count: Option<u64>,
+    /// Set by `Logger::error_with`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<ErrorDetails>,
+    /// Collected once per `Logger`, see `MetadataConfig`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub host: Option<HostMetadata>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thread: Option<ThreadMetadata>,
+    /// Flattened into `trace_id`, `span_id` and `trace_flags`.
+    #[serde(flatten)]
+    pub trace: Option<TraceContext>,
 }
 
 impl Display for BetterStackLogSchema {
@@ -28,26 +56,48 @@ impl BetterStackLogSchema {
         context: String,
     ) -> Self {
         Self {
+            dt: SystemTime::now(),
             env: env_config.environment.clone(),
             message,
             context,
             level,
             app_version: env_config.app_version.clone(),
+            repeat_count: None,
+            error: None,
+            host: None,
+            thread: None,
+            trace: None,
+            service: env_config.service.clone(),
+            labels: env_config.labels.clone(),
+        }
+    }
+
+    /// A QA record with context `ctx` at the Unix epoch, for tests.
+    #[cfg(test)]
+    pub(crate) fn fixture(level: LogLevel, message: &str) -> Self {
+        let config =
+            EnvConfig::from_values("1.0.0".to_string(), EnvEnum::QA, "token".to_string(), false);
+        Self {
+            dt: std::time::UNIX_EPOCH,
+            ..Self::new(&config, level, message.to_string(), "ctx".to_string())
         }
     }
 }
 
+fn serialize_dt<S: Serializer>(dt: &SystemTime, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&format_rfc3339(*dt))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::time::{Duration, UNIX_EPOCH};
 
     fn sample_schema() -> BetterStackLogSchema {
         BetterStackLogSchema {
-            env: EnvEnum::QA,
-            message: "test message".to_string(),
+            dt: UNIX_EPOCH + Duration::from_millis(1_728_030_600_250),
             context: "test context".to_string(),
-            level: LogLevel::Info,
-            app_version: "1.0.0".to_string(),
+            ..BetterStackLogSchema::fixture(LogLevel::Info, "test message")
         }
     }
 
@@ -66,6 +116,7 @@ mod tests {
         let parsed: serde_json::Value = serde_json::from_str(&output).unwrap();
         let obj = parsed.as_object().unwrap();
 
+        assert!(obj.contains_key("dt"));
         assert!(obj.contains_key("env"));
         assert!(obj.contains_key("message"));
         assert!(obj.contains_key("context"));
@@ -84,6 +135,7 @@ mod tests {
         assert_eq!(parsed["app_version"], "1.0.0");
         assert_eq!(parsed["env"], "QA");
         assert_eq!(parsed["level"], "Info");
+        assert_eq!(parsed["dt"], "2024-10-04T08:30:00.250Z");
     }
 
     #[test]
@@ -91,6 +143,7 @@ mod tests {
         let schema = sample_schema();
         let cloned = schema.clone();
 
+        assert_eq!(schema.dt, cloned.dt);
         assert_eq!(schema.env, cloned.env);
         assert_eq!(schema.message, cloned.message);
         assert_eq!(schema.context, cloned.context);
@@ -104,7 +157,8 @@ mod tests {
         let value = serde_json::to_value(&schema).unwrap();
         let obj = value.as_object().unwrap();
 
-        assert_eq!(obj.len(), 5);
+        assert_eq!(obj.len(), 6);
+        assert_eq!(obj["dt"], "2024-10-04T08:30:00.250Z");
         assert_eq!(obj["message"], "test message");
         assert_eq!(obj["context"], "test context");
         assert_eq!(obj["level"], "Info");
@@ -113,14 +167,57 @@ mod tests {
     }
 
     #[test]
-    fn new_maps_fields_from_env_config() {
-        let config = EnvConfig {
-            app_version: "2.5.0".to_string(),
-            environment: EnvEnum::Prod,
-            logs_source_token: "token".to_string(),
-            verbose: false,
+    fn serde_serialize_includes_service_and_labels_when_set() {
+        let schema = BetterStackLogSchema {
+            service: Some("billing".to_string()),
+            labels: BTreeMap::from([("team".to_string(), "payments".to_string())]),
+            ..sample_schema()
+        };
+        let value = serde_json::to_value(&schema).unwrap();
+
+        assert_eq!(value["service"], "billing");
+        assert_eq!(value["labels"]["team"], "payments");
+    }
+
+    #[test]
+    fn serde_serialize_flattens_trace_context() {
+        let schema = BetterStackLogSchema {
+            trace: TraceContext::from_traceparent(
+                "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+            ),
+            ..sample_schema()
         };
+        let value = serde_json::to_value(&schema).unwrap();
+
+        assert_eq!(value["trace_id"], "4bf92f3577b34da6a3ce929d0e0e4736");
+        assert_eq!(value["span_id"], "00f067aa0ba902b7");
+        assert_eq!(value["trace_flags"], "01");
+        assert!(value.get("trace").is_none());
+    }
+
+    #[test]
+    fn serde_serialize_includes_repeat_count_when_set() {
+        let schema = BetterStackLogSchema {
+            repeat_count: Some(42),
+            ..sample_schema()
+        };
+        let value = serde_json::to_value(&schema).unwrap();
+
+        assert_eq!(value["repeat_count"], 42);
+    }
+
+    #[test]
+    fn new_maps_fields_from_env_config() {
+        let mut config = EnvConfig::from_values(
+            "2.5.0".to_string(),
+            EnvEnum::Prod,
+            "token".to_string(),
+            false,
+        );
+        config.service = Some("billing".to_string());
+        config.labels = BTreeMap::from([("region".to_string(), "eu-west-1".to_string())]);
 
+        let before = SystemTime::now();
         let schema = BetterStackLogSchema::new(
             &config,
             LogLevel::Error,
@@ -131,7 +228,10 @@ mod tests {
         assert_eq!(schema.env, EnvEnum::Prod);
         assert_eq!(schema.message, "err msg");
         assert_eq!(schema.context, "err ctx");
+        assert_eq!(schema.service.as_deref(), Some("billing"));
+        assert_eq!(schema.labels["region"], "eu-west-1");
         assert_eq!(schema.level, LogLevel::Error);
         assert_eq!(schema.app_version, "2.5.0");
+        assert!(schema.dt >= before && schema.dt <= SystemTime::now());
     }
 }
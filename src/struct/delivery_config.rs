@@ -0,0 +1,78 @@
+use crate::http_client::{CircuitBreakerConfig, SizeLimits};
+use crate::sink::{RateLimitConfig, SamplingConfig};
+use std::time::Duration;
+
+/// How hard `push_log` tries before giving up on a record.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeliveryConfig {
+    /// Extra attempts after a network error, a timeout, a `429` or a `5xx`.
+    pub retries: u32,
+    /// Wait before the first retry, doubled for every following one.
+    pub retry_backoff: Duration,
+    /// Deadline for the whole delivery, retries and backoff included.
+    pub total_timeout: Option<Duration>,
+    /// Stops calling BetterStack after repeated failures, `None` always tries.
+    pub circuit_breaker: Option<CircuitBreakerConfig>,
+    /// Caps how many records are shipped, `None` ships them all.
+    pub rate_limit: Option<RateLimitConfig>,
+    /// Ships a share of each level, applied before `rate_limit`.
+    pub sampling: Option<SamplingConfig>,
+    /// How often a summary of the records dropped by `sampling` and `rate_limit` is shipped.
+    pub summary_interval: Duration,
+    /// Where records are truncated and batches split.
+    pub size_limits: SizeLimits,
+}
+
+impl Default for DeliveryConfig {
+    /// A single attempt, abandoned after 30 seconds, behind the default circuit breaker.
+    fn default() -> Self {
+        Self {
+            retries: 0,
+            retry_backoff: Duration::from_millis(200),
+            total_timeout: Some(Duration::from_secs(30)),
+            circuit_breaker: Some(CircuitBreakerConfig::default()),
+            rate_limit: None,
+            sampling: None,
+            summary_interval: Duration::from_secs(60),
+            size_limits: SizeLimits::default(),
+        }
+    }
+}
+
+impl DeliveryConfig {
+    /// Backoff before retry number `retry`, starting at 1.
+    pub(crate) fn backoff(&self, retry: u32) -> Duration {
+        self.retry_backoff
+            .saturating_mul(2u32.saturating_pow(retry.saturating_sub(1)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_is_single_attempt_with_deadline() {
+        let config = DeliveryConfig::default();
+        assert_eq!(config.retries, 0);
+        assert_eq!(config.total_timeout, Some(Duration::from_secs(30)));
+    }
+
+    #[test]
+    fn backoff_doubles() {
+        let config = DeliveryConfig {
+            retry_backoff: Duration::from_millis(100),
+            ..DeliveryConfig::default()
+        };
+        assert_eq!(config.backoff(1), Duration::from_millis(100));
+        assert_eq!(config.backoff(2), Duration::from_millis(200));
+        assert_eq!(config.backoff(3), Duration::from_millis(400));
+    }
+
+    #[test]
+    fn backoff_saturates() {
+        let config = DeliveryConfig::default();
+        assert_eq!(config.backoff(64), config.backoff(33));
+        assert!(config.backoff(64) > config.backoff(32));
+    }
+}
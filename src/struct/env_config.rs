@@ -1,5 +1,11 @@
+use crate::r#struct::delivery_config::DeliveryConfig;
+use crate::r#struct::metadata_config::MetadataConfig;
+use crate::r#struct::output_config::OutputConfig;
+use crate::r#struct::redaction_config::RedactionConfig;
 use serde::Serialize;
+use std::collections::BTreeMap;
 use std::env;
+use std::ffi::OsString;
 use std::str::FromStr;
 use strum_macros::{Display, EnumString};
 
@@ -15,11 +21,29 @@ pub enum EnvEnum {
     Prod,
 }
 
+/// Environment variables starting with this become labels, e.g. `LOGTAIL_LABEL_REGION`
+/// sets the `region` label.
+pub const LABEL_VAR_PREFIX: &str = "LOGTAIL_LABEL_";
+
+/// BetterStack's shared ingestion endpoint.
+pub const DEFAULT_LOGS_URL: &str = "https://in.logs.betterstack.com";
+
+#[derive(Clone)]
 pub struct EnvConfig {
     pub app_version: String,
     pub environment: EnvEnum,
     pub logs_source_token: String,
+    /// Where logs are pushed, e.g. a source-specific ingesting host or a local test server.
+    pub logs_url: String,
     pub verbose: bool,
+    /// Tells apart services sharing one BetterStack source.
+    pub service: Option<String>,
+    /// Static labels such as region or team, attached to every record.
+    pub labels: BTreeMap<String, String>,
+    pub output: OutputConfig,
+    pub delivery: DeliveryConfig,
+    pub redaction: RedactionConfig,
+    pub metadata: MetadataConfig,
 }
 
 impl Default for EnvConfig {
@@ -36,11 +60,19 @@ impl EnvConfig {
         logs_source_token: String,
         verbose: bool,
     ) -> Self {
+        let output = OutputConfig::from_env(&environment, verbose);
         EnvConfig {
             app_version,
             environment,
             logs_source_token,
+            logs_url: DEFAULT_LOGS_URL.to_string(),
             verbose,
+            service: None,
+            labels: BTreeMap::new(),
+            output,
+            delivery: DeliveryConfig::default(),
+            redaction: RedactionConfig::default(),
+            metadata: MetadataConfig::default(),
         }
     }
 
@@ -50,14 +82,61 @@ impl EnvConfig {
         let environment = EnvEnum::from_str(&environment_string).unwrap();
         let logs_source_token =
             env::var("LOGS_SOURCE_TOKEN").expect("missing variable: LOGS_SOURCE_TOKEN");
+        let logs_url = env::var("LOGS_URL").unwrap_or_else(|_| DEFAULT_LOGS_URL.to_string());
+
+        let mut output = OutputConfig::from_env(&environment, verbose);
+        if let Some(remote) = bool_var("LOGS_REMOTE") {
+            output.remote = remote;
+        }
+        if let Some(console) = bool_var("LOGS_CONSOLE") {
+            output.console = console;
+        }
 
         EnvConfig {
             app_version,
             environment,
             logs_source_token,
+            logs_url,
             verbose,
+            service: env::var("LOGS_SERVICE_NAME").ok(),
+            labels: label_vars(env::vars_os()),
+            output,
+            delivery: DeliveryConfig::default(),
+            redaction: RedactionConfig::default(),
+            metadata: MetadataConfig::default(),
         }
     }
+
+    pub fn with_service(mut self, service: impl Into<String>) -> Self {
+        self.service = Some(service.into());
+        self
+    }
+
+    pub fn with_label(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.labels.insert(key.into(), value.into());
+        self
+    }
+}
+
+/// Labels from the variables starting with [`LABEL_VAR_PREFIX`], keyed by the lowercased rest.
+/// Variables that are not valid UTF-8 are skipped.
+fn label_vars(vars: impl Iterator<Item = (OsString, OsString)>) -> BTreeMap<String, String> {
+    vars.filter_map(|(name, value)| {
+        let (name, value) = (name.into_string().ok()?, value.into_string().ok()?);
+        let key = name.strip_prefix(LABEL_VAR_PREFIX)?;
+        (!key.is_empty()).then(|| (key.to_lowercase(), value))
+    })
+    .collect()
+}
+
+/// Reads an optional `true`/`false` variable, panicking on any other value.
+fn bool_var(name: &str) -> Option<bool> {
+    let value = env::var(name).ok()?;
+    Some(
+        value
+            .parse()
+            .unwrap_or_else(|_| panic!("invalid variable: {}", name)),
+    )
 }
 
 #[cfg(test)]
@@ -123,7 +202,53 @@ mod tests {
         assert_eq!(config.app_version, "1.2.3");
         assert_eq!(config.environment, EnvEnum::Prod);
         assert_eq!(config.logs_source_token, "my-token");
+        assert_eq!(config.logs_url, DEFAULT_LOGS_URL);
         assert!(!config.verbose);
+        assert_eq!(config.output, OutputConfig::from_env(&EnvEnum::Prod, false));
+    }
+
+    #[test]
+    fn builders_set_service_and_labels() {
+        let config =
+            EnvConfig::from_values("1.0.0".to_string(), EnvEnum::QA, "token".to_string(), false)
+                .with_service("billing")
+                .with_label("region", "eu-west-1");
+
+        assert_eq!(config.service.as_deref(), Some("billing"));
+        assert_eq!(config.labels["region"], "eu-west-1");
+    }
+
+    #[test]
+    fn label_vars_strip_prefix_and_lowercase() {
+        let vars = [
+            ("LOGTAIL_LABEL_REGION", "eu-west-1"),
+            ("LOGTAIL_LABEL_TEAM", "payments"),
+            ("LOGTAIL_LABEL_", "ignored"),
+            ("REGION", "ignored"),
+        ];
+
+        let labels = label_vars(vars.into_iter().map(|(k, v)| (k.into(), v.into())));
+
+        assert_eq!(labels.len(), 2);
+        assert_eq!(labels["region"], "eu-west-1");
+        assert_eq!(labels["team"], "payments");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn label_vars_skip_non_utf8() {
+        use std::os::unix::ffi::OsStringExt;
+
+        let vars = vec![
+            ("LOGTAIL_LABEL_REGION".into(), "eu-west-1".into()),
+            ("LOGTAIL_LABEL_TEAM".into(), OsString::from_vec(vec![0xff])),
+            (OsString::from_vec(vec![0xff]), "ignored".into()),
+        ];
+
+        let labels = label_vars(vars.into_iter());
+
+        assert_eq!(labels.len(), 1);
+        assert_eq!(labels["region"], "eu-west-1");
     }
 
     // --- EnvConfig::new tests (env-var dependent, must run serially) ---
@@ -142,6 +267,86 @@ mod tests {
         assert!(config.verbose);
     }
 
+    #[test]
+    #[serial]
+    fn new_reads_optional_logs_url() {
+        env::set_var("ENVIRONMENT", "qa");
+        env::set_var("LOGS_SOURCE_TOKEN", "token");
+
+        env::remove_var("LOGS_URL");
+        assert_eq!(
+            EnvConfig::new("1.0.0".to_string(), false).logs_url,
+            DEFAULT_LOGS_URL
+        );
+
+        env::set_var("LOGS_URL", "http://127.0.0.1:8080");
+        let config = EnvConfig::new("1.0.0".to_string(), false);
+        env::remove_var("LOGS_URL");
+        assert_eq!(config.logs_url, "http://127.0.0.1:8080");
+    }
+
+    #[test]
+    #[serial]
+    fn new_reads_service_and_labels() {
+        env::set_var("ENVIRONMENT", "qa");
+        env::set_var("LOGS_SOURCE_TOKEN", "token");
+        env::set_var("LOGS_SERVICE_NAME", "billing");
+        env::set_var("LOGTAIL_LABEL_REGION", "eu-west-1");
+
+        let config = EnvConfig::new("1.0.0".to_string(), false);
+        env::remove_var("LOGS_SERVICE_NAME");
+        env::remove_var("LOGTAIL_LABEL_REGION");
+
+        assert_eq!(config.service.as_deref(), Some("billing"));
+        assert_eq!(config.labels["region"], "eu-west-1");
+    }
+
+    #[test]
+    #[serial]
+    fn new_defaults_output_from_environment() {
+        env::set_var("ENVIRONMENT", "local");
+        env::set_var("LOGS_SOURCE_TOKEN", "token");
+        env::remove_var("LOGS_REMOTE");
+        env::remove_var("LOGS_CONSOLE");
+
+        let config = EnvConfig::new("1.0.0".to_string(), true);
+
+        assert!(!config.output.remote);
+        assert!(config.output.console);
+    }
+
+    #[test]
+    #[serial]
+    fn new_reads_output_overrides() {
+        env::set_var("ENVIRONMENT", "local");
+        env::set_var("LOGS_SOURCE_TOKEN", "token");
+        env::set_var("LOGS_REMOTE", "true");
+        env::set_var("LOGS_CONSOLE", "false");
+
+        let config = EnvConfig::new("1.0.0".to_string(), true);
+        env::remove_var("LOGS_REMOTE");
+        env::remove_var("LOGS_CONSOLE");
+
+        assert_eq!(config.environment, EnvEnum::Local);
+        assert!(config.output.remote);
+        assert!(!config.output.console);
+    }
+
+    #[test]
+    #[serial]
+    #[should_panic(expected = "invalid variable: LOGS_REMOTE")]
+    fn new_panics_invalid_output_override() {
+        env::set_var("ENVIRONMENT", "qa");
+        env::set_var("LOGS_SOURCE_TOKEN", "token");
+        env::set_var("LOGS_REMOTE", "yes");
+
+        let result = std::panic::catch_unwind(|| EnvConfig::new("1.0.0".to_string(), false));
+        env::remove_var("LOGS_REMOTE");
+        if let Err(panic) = result {
+            std::panic::resume_unwind(panic);
+        }
+    }
+
     #[test]
     #[serial]
     fn default_uses_cargo_version() {
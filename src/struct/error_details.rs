@@ -0,0 +1,118 @@
+use crate::r#struct::redaction_config::RedactionConfig;
+use serde::Serialize;
+use std::backtrace::Backtrace;
+use std::error::Error;
+
+/// An error attached to a record by `Logger::error_with`.
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct ErrorDetails {
+    /// The error's `Display` text.
+    pub message: String,
+    /// `Display` text of each `source()`, outermost first.
+    pub sources: Vec<String>,
+    /// Where the error was logged, not where it was created.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub backtrace: Option<String>,
+}
+
+impl ErrorDetails {
+    pub fn new(error: &dyn Error, capture_backtrace: bool) -> Self {
+        let mut sources = Vec::new();
+        let mut source = error.source();
+        while let Some(err) = source {
+            sources.push(err.to_string());
+            source = err.source();
+        }
+        Self {
+            message: error.to_string(),
+            sources,
+            backtrace: capture_backtrace.then(|| Backtrace::force_capture().to_string()),
+        }
+    }
+
+    pub(crate) fn redacted(self, redaction: &RedactionConfig) -> Self {
+        Self {
+            message: redaction.redact(&self.message),
+            sources: self
+                .sources
+                .iter()
+                .map(|source| redaction.redact(source))
+                .collect(),
+            backtrace: self.backtrace,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fmt;
+
+    #[derive(Debug)]
+    struct Layer(&'static str, Option<Box<Layer>>);
+
+    impl fmt::Display for Layer {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "{}", self.0)
+        }
+    }
+
+    impl Error for Layer {
+        fn source(&self) -> Option<&(dyn Error + 'static)> {
+            self.1
+                .as_deref()
+                .map(|layer| layer as &(dyn Error + 'static))
+        }
+    }
+
+    fn chain() -> Layer {
+        Layer(
+            "request failed",
+            Some(Box::new(Layer(
+                "connection reset",
+                Some(Box::new(Layer("os error 104", None))),
+            ))),
+        )
+    }
+
+    #[test]
+    fn records_message_and_source_chain() {
+        let details = ErrorDetails::new(&chain(), false);
+
+        assert_eq!(details.message, "request failed");
+        assert_eq!(details.sources, vec!["connection reset", "os error 104"]);
+        assert_eq!(details.backtrace, None);
+    }
+
+    #[test]
+    fn captures_backtrace_on_request() {
+        let details = ErrorDetails::new(&chain(), true);
+
+        assert!(details.backtrace.is_some());
+    }
+
+    #[test]
+    fn serializes_sources_as_array() {
+        let value = serde_json::to_value(ErrorDetails::new(&chain(), false)).unwrap();
+
+        assert_eq!(value["message"], "request failed");
+        assert_eq!(
+            value["sources"],
+            serde_json::json!(["connection reset", "os error 104"])
+        );
+        assert!(value.get("backtrace").is_none());
+    }
+
+    #[test]
+    fn redacted_masks_message_and_sources() {
+        let error = Layer(
+            "login failed with token=abc",
+            Some(Box::new(Layer("password=hunter2 rejected", None))),
+        );
+
+        let details = ErrorDetails::new(&error, false).redacted(&RedactionConfig::default());
+
+        assert_eq!(details.message, "login failed with token=[REDACTED]");
+        assert_eq!(details.sources, vec!["password=[REDACTED] rejected"]);
+    }
+}
@@ -1,16 +1,17 @@
 use serde::Serialize;
 use strum_macros::{Display, EnumString};
 
-#[derive(Debug, EnumString, Display, PartialEq, Serialize, Clone)]
+/// Severity of a record, ordered from least (`Debug`) to most (`Error`) severe.
+#[derive(Debug, EnumString, Display, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Clone)]
 pub enum LogLevel {
+    #[strum(serialize = "debug")]
+    Debug,
     #[strum(serialize = "info")]
     Info,
     #[strum(serialize = "warn")]
     Warn,
     #[strum(serialize = "error")]
     Error,
-    #[strum(serialize = "debug")]
-    Debug,
 }
 
 #[cfg(test)]
@@ -48,6 +49,13 @@ mod tests {
         assert_ne!(LogLevel::Error, LogLevel::Debug);
     }
 
+    #[test]
+    fn ordering_by_severity() {
+        assert!(LogLevel::Debug < LogLevel::Info);
+        assert!(LogLevel::Info < LogLevel::Warn);
+        assert!(LogLevel::Warn < LogLevel::Error);
+    }
+
     #[test]
     fn serde_json_serialize() {
         assert_eq!(serde_json::to_string(&LogLevel::Info).unwrap(), "\"Info\"");
@@ -13,8 +13,8 @@ impl LogSchema {
         BetterStackLogSchema::new(
             env_config,
             level,
-            self.message.clone(),
-            self.context.clone(),
+            env_config.redaction.redact(&self.message),
+            env_config.redaction.redact(&self.context),
         )
     }
 }
@@ -23,14 +23,15 @@ impl LogSchema {
 mod tests {
     use super::*;
     use crate::r#struct::env_config::{EnvConfig, EnvEnum};
+    use crate::r#struct::redaction_config::RedactionConfig;
 
     fn test_config() -> EnvConfig {
-        EnvConfig {
-            app_version: "0.1.0".to_string(),
-            environment: EnvEnum::QA,
-            logs_source_token: "test-token".to_string(),
-            verbose: false,
-        }
+        EnvConfig::from_values(
+            "0.1.0".to_string(),
+            EnvEnum::QA,
+            "test-token".to_string(),
+            false,
+        )
     }
 
     #[test]
@@ -103,4 +104,28 @@ mod tests {
         let result = log.to_betterstack(&config, LogLevel::Info);
         assert_eq!(result.app_version, "3.2.1");
     }
+
+    #[test]
+    fn to_betterstack_redacts_message_and_context() {
+        let log = LogSchema {
+            message: "reset with api_key=abc".to_string(),
+            context: "password=hunter2".to_string(),
+        };
+        let result = log.to_betterstack(&test_config(), LogLevel::Info);
+        assert_eq!(result.message, "reset with api_key=[REDACTED]");
+        assert_eq!(result.context, "password=[REDACTED]");
+    }
+
+    #[test]
+    fn to_betterstack_keeps_text_without_redaction() {
+        let mut config = test_config();
+        config.redaction = RedactionConfig::none();
+
+        let log = LogSchema {
+            message: "reset with api_key=abc".to_string(),
+            context: "ctx".to_string(),
+        };
+        let result = log.to_betterstack(&config, LogLevel::Info);
+        assert_eq!(result.message, "reset with api_key=abc");
+    }
 }
@@ -0,0 +1,272 @@
+use serde::Serialize;
+use std::env;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Which host, process and thread details are attached to every record. The hostname,
+/// pid and thread are attached by default, the rest on request, see [`MetadataConfig::all`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct MetadataConfig {
+    pub hostname: bool,
+    pub pid: bool,
+    pub process_name: bool,
+    pub thread: bool,
+    pub os: bool,
+    pub arch: bool,
+    /// `POD_NAME`, `POD_NAMESPACE`, `NODE_NAME` and `CONTAINER_NAME`, when set.
+    pub kubernetes: bool,
+}
+
+impl Default for MetadataConfig {
+    fn default() -> Self {
+        Self {
+            hostname: true,
+            pid: true,
+            thread: true,
+            ..Self::none()
+        }
+    }
+}
+
+impl MetadataConfig {
+    /// Attaches every detail.
+    pub fn all() -> Self {
+        Self {
+            hostname: true,
+            pid: true,
+            process_name: true,
+            thread: true,
+            os: true,
+            arch: true,
+            kubernetes: true,
+        }
+    }
+
+    /// Attaches nothing.
+    pub fn none() -> Self {
+        Self {
+            hostname: false,
+            pid: false,
+            process_name: false,
+            thread: false,
+            os: false,
+            arch: false,
+            kubernetes: false,
+        }
+    }
+}
+
+/// Details of the machine and process, collected once when the `Logger` is built.
+#[derive(Debug, Serialize, Clone, PartialEq, Default)]
+pub struct HostMetadata {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hostname: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pid: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub process_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub os: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub arch: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pod_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pod_namespace: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub node_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub container_name: Option<String>,
+}
+
+impl HostMetadata {
+    /// `None` when `config` selects none of the host details.
+    pub(crate) fn collect(config: &MetadataConfig) -> Option<Self> {
+        Self::collect_with(
+            config,
+            |name| env::var(name).ok(),
+            |path| std::fs::read_to_string(path).ok(),
+        )
+    }
+
+    fn collect_with(
+        config: &MetadataConfig,
+        var: impl Fn(&str) -> Option<String>,
+        read: impl Fn(&str) -> Option<String>,
+    ) -> Option<Self> {
+        let metadata = Self {
+            hostname: config.hostname.then(|| hostname(&var, read)).flatten(),
+            pid: config.pid.then(std::process::id),
+            process_name: config.process_name.then(process_name).flatten(),
+            os: config.os.then(|| env::consts::OS.to_string()),
+            arch: config.arch.then(|| env::consts::ARCH.to_string()),
+            pod_name: config.kubernetes.then(|| var("POD_NAME")).flatten(),
+            pod_namespace: config.kubernetes.then(|| var("POD_NAMESPACE")).flatten(),
+            node_name: config.kubernetes.then(|| var("NODE_NAME")).flatten(),
+            container_name: config.kubernetes.then(|| var("CONTAINER_NAME")).flatten(),
+        };
+        (metadata != Self::default()).then_some(metadata)
+    }
+}
+
+/// The thread a record was logged from.
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct ThreadMetadata {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// A number unique to the thread within the process, given out on its first record.
+    pub id: String,
+}
+
+static NEXT_THREAD_ID: AtomicU64 = AtomicU64::new(1);
+
+thread_local! {
+    static THREAD_ID: u64 = NEXT_THREAD_ID.fetch_add(1, Ordering::Relaxed);
+}
+
+impl ThreadMetadata {
+    pub(crate) fn current() -> Self {
+        let thread = std::thread::current();
+        Self {
+            name: thread.name().map(ToString::to_string),
+            id: THREAD_ID.with(|id| id.to_string()),
+        }
+    }
+}
+
+/// `HOSTNAME` or `COMPUTERNAME`, falling back to the `/etc/hostname` file.
+fn hostname(
+    var: impl Fn(&str) -> Option<String>,
+    read: impl Fn(&str) -> Option<String>,
+) -> Option<String> {
+    var("HOSTNAME")
+        .or_else(|| var("COMPUTERNAME"))
+        .or_else(|| read("/etc/hostname"))
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+}
+
+fn process_name() -> Option<String> {
+    let exe = env::current_exe().ok()?;
+    Some(exe.file_name()?.to_string_lossy().into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn vars(pairs: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let vars: HashMap<String, String> = pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        move |name| vars.get(name).cloned()
+    }
+
+    fn no_files(_: &str) -> Option<String> {
+        None
+    }
+
+    #[test]
+    fn all_collects_everything() {
+        let metadata = HostMetadata::collect_with(
+            &MetadataConfig::all(),
+            vars(&[("HOSTNAME", "web-1"), ("POD_NAME", "web-1-abc")]),
+            no_files,
+        )
+        .unwrap();
+
+        assert_eq!(metadata.hostname.as_deref(), Some("web-1"));
+        assert_eq!(metadata.pid, Some(std::process::id()));
+        assert!(metadata.process_name.is_some());
+        assert_eq!(metadata.os.as_deref(), Some(env::consts::OS));
+        assert_eq!(metadata.arch.as_deref(), Some(env::consts::ARCH));
+        assert_eq!(metadata.pod_name.as_deref(), Some("web-1-abc"));
+        assert_eq!(metadata.pod_namespace, None);
+    }
+
+    #[test]
+    fn toggles_are_respected() {
+        let config = MetadataConfig {
+            pid: false,
+            kubernetes: false,
+            ..MetadataConfig::all()
+        };
+
+        let metadata =
+            HostMetadata::collect_with(&config, vars(&[("POD_NAME", "web-1-abc")]), no_files)
+                .unwrap();
+
+        assert_eq!(metadata.pid, None);
+        assert_eq!(metadata.pod_name, None);
+        assert!(metadata.os.is_some());
+    }
+
+    #[test]
+    fn default_collects_hostname_and_pid() {
+        let metadata = HostMetadata::collect_with(
+            &MetadataConfig::default(),
+            vars(&[("HOSTNAME", "web-1"), ("POD_NAME", "web-1-abc")]),
+            no_files,
+        )
+        .unwrap();
+
+        assert_eq!(
+            metadata,
+            HostMetadata {
+                hostname: Some("web-1".to_string()),
+                pid: Some(std::process::id()),
+                ..HostMetadata::default()
+            }
+        );
+        assert!(MetadataConfig::default().thread);
+    }
+
+    #[test]
+    fn none_collects_nothing() {
+        let metadata = HostMetadata::collect_with(&MetadataConfig::none(), vars(&[]), no_files);
+
+        assert_eq!(metadata, None);
+    }
+
+    #[test]
+    fn hostname_falls_back_to_file() {
+        let config = MetadataConfig {
+            hostname: true,
+            ..MetadataConfig::none()
+        };
+        let read = |path: &str| (path == "/etc/hostname").then(|| "db-2\n".to_string());
+
+        let metadata = HostMetadata::collect_with(&config, vars(&[]), read).unwrap();
+
+        assert_eq!(metadata.hostname.as_deref(), Some("db-2"));
+    }
+
+    #[test]
+    fn serializes_only_collected_fields() {
+        let config = MetadataConfig {
+            os: true,
+            ..MetadataConfig::none()
+        };
+        let metadata = HostMetadata::collect_with(&config, vars(&[]), no_files).unwrap();
+
+        let value = serde_json::to_value(metadata).unwrap();
+
+        assert_eq!(value, serde_json::json!({ "os": env::consts::OS }));
+    }
+
+    #[test]
+    fn thread_ids_are_stable_and_distinct() {
+        let thread = std::thread::Builder::new()
+            .name("worker".to_string())
+            .spawn(|| (ThreadMetadata::current(), ThreadMetadata::current()))
+            .unwrap()
+            .join()
+            .unwrap();
+
+        assert_eq!(thread.0.name.as_deref(), Some("worker"));
+        assert_eq!(thread.0.id, thread.1.id);
+        assert_ne!(thread.0.id, ThreadMetadata::current().id);
+        assert!(thread.0.id.parse::<u64>().is_ok());
+    }
+}
@@ -1,4 +1,10 @@
 pub mod betterstack_log_schema;
+pub mod delivery_config;
 pub mod env_config;
+pub mod error_details;
 pub mod log_level;
 pub mod log_schema;
+pub mod metadata_config;
+pub mod output_config;
+pub mod redaction_config;
+pub mod trace_context;
@@ -0,0 +1,45 @@
+use crate::r#struct::env_config::EnvEnum;
+
+/// Selects where a `Logger` writes its records, independently of the environment label.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutputConfig {
+    /// Ship records to BetterStack.
+    pub remote: bool,
+    /// Print records to the console.
+    pub console: bool,
+}
+
+impl OutputConfig {
+    /// Historical behavior: ship from every environment except `local`, print when `verbose`.
+    pub fn from_env(environment: &EnvEnum, verbose: bool) -> Self {
+        Self {
+            remote: *environment != EnvEnum::Local,
+            console: verbose,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn local_env_disables_remote() {
+        let output = OutputConfig::from_env(&EnvEnum::Local, true);
+        assert!(!output.remote);
+        assert!(output.console);
+    }
+
+    #[test]
+    fn non_local_envs_enable_remote() {
+        for env in [EnvEnum::QA, EnvEnum::PreProd, EnvEnum::Prod] {
+            assert!(OutputConfig::from_env(&env, false).remote);
+        }
+    }
+
+    #[test]
+    fn console_follows_verbose() {
+        assert!(OutputConfig::from_env(&EnvEnum::QA, true).console);
+        assert!(!OutputConfig::from_env(&EnvEnum::QA, false).console);
+    }
+}
@@ -0,0 +1,302 @@
+use regex::{Captures, Regex};
+use std::sync::LazyLock;
+
+/// Key names whose values are masked by default, matched case-insensitively.
+pub const DEFAULT_REDACTED_KEYS: &[&str] = &[
+    "password",
+    "passwd",
+    "secret",
+    "token",
+    "api_key",
+    "apikey",
+    "authorization",
+    "cookie",
+];
+
+pub const DEFAULT_MASK: &str = "[REDACTED]";
+
+static EMAIL: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"[A-Za-z0-9._%+-]+@[A-Za-z0-9-]+(?:\.[A-Za-z0-9-]+)+").unwrap());
+
+/// 13 to 19 digits, optionally grouped by spaces or dashes; confirmed with a Luhn check.
+static CARD_NUMBER: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\b\d(?:[ -]?\d){12,18}\b").unwrap());
+
+/// What is masked in `message` and `context` before a record reaches any sink.
+#[derive(Debug, Clone)]
+pub struct RedactionConfig {
+    keys: Vec<String>,
+    keys_pattern: Option<Regex>,
+    patterns: Vec<Regex>,
+    emails: bool,
+    credit_cards: bool,
+    mask: String,
+}
+
+impl Default for RedactionConfig {
+    /// Masks [`DEFAULT_REDACTED_KEYS`]. Email and card number masking are opt-in, as they
+    /// also catch identifiers and timestamps that merely look alike.
+    fn default() -> Self {
+        Self::none().with_keys(DEFAULT_REDACTED_KEYS.iter().copied())
+    }
+}
+
+impl RedactionConfig {
+    /// Masks nothing, a starting point for a custom set of rules.
+    pub fn none() -> Self {
+        Self {
+            keys: Vec::new(),
+            keys_pattern: None,
+            patterns: Vec::new(),
+            emails: false,
+            credit_cards: false,
+            mask: DEFAULT_MASK.to_string(),
+        }
+    }
+
+    /// Masks the value following any of `keys` and a `:` or `=`, as in `password=hunter2`,
+    /// `"token": "abc"` or `Authorization: Bearer abc`. A quoted value is masked up to its
+    /// closing quote. A key also matches at the end of a longer name such as `access_token`
+    /// or `client-secret`.
+    pub fn with_keys<S: Into<String>>(mut self, keys: impl IntoIterator<Item = S>) -> Self {
+        self.keys.extend(keys.into_iter().map(Into::into));
+        if self.keys.is_empty() {
+            return self;
+        }
+        let alternatives: Vec<String> = self.keys.iter().map(|key| regex::escape(key)).collect();
+        self.keys_pattern = Some(
+            Regex::new(&format!(
+                r#"(?i)((?:^|[^A-Za-z0-9])(?:{})["']?\s*[:=]\s*)("[^"]*"?|'[^']*'?|(?:bearer |basic )?[^\s"',;&}}]+)"#,
+                alternatives.join("|")
+            ))
+            .unwrap(),
+        );
+        self
+    }
+
+    /// Masks every match of `pattern`.
+    pub fn with_pattern(mut self, pattern: &str) -> Result<Self, regex::Error> {
+        self.patterns.push(Regex::new(pattern)?);
+        Ok(self)
+    }
+
+    /// Masks email addresses, off by default.
+    pub fn with_emails(mut self, emails: bool) -> Self {
+        self.emails = emails;
+        self
+    }
+
+    /// Masks card numbers that pass a Luhn check, off by default. Any 13 to 19 digit
+    /// number can pass, so ids and timestamps are masked now and then.
+    pub fn with_credit_cards(mut self, credit_cards: bool) -> Self {
+        self.credit_cards = credit_cards;
+        self
+    }
+
+    /// Replaces what is masked, [`DEFAULT_MASK`] by default.
+    pub fn with_mask(mut self, mask: impl Into<String>) -> Self {
+        self.mask = mask.into();
+        self
+    }
+
+    pub fn redact(&self, text: &str) -> String {
+        let mut text = text.to_string();
+        if let Some(keys) = &self.keys_pattern {
+            text = keys
+                .replace_all(&text, |caps: &Captures| {
+                    format!("{}{}", &caps[1], quoted(&caps[2], &self.mask))
+                })
+                .into_owned();
+        }
+        for pattern in &self.patterns {
+            text = pattern.replace_all(&text, self.mask.as_str()).into_owned();
+        }
+        if self.emails {
+            text = EMAIL.replace_all(&text, self.mask.as_str()).into_owned();
+        }
+        if self.credit_cards {
+            text = CARD_NUMBER
+                .replace_all(&text, |caps: &Captures| {
+                    if luhn_valid(&caps[0]) {
+                        self.mask.clone()
+                    } else {
+                        caps[0].to_string()
+                    }
+                })
+                .into_owned();
+        }
+        text
+    }
+}
+
+/// `mask` inside the quotes `value` opened and closed, if any.
+fn quoted(value: &str, mask: &str) -> String {
+    let Some(quote) = value.chars().next().filter(|c| *c == '"' || *c == '\'') else {
+        return mask.to_string();
+    };
+    let close = if value.len() > 1 && value.ends_with(quote) {
+        quote.to_string()
+    } else {
+        String::new()
+    };
+    format!("{}{}{}", quote, mask, close)
+}
+
+fn luhn_valid(number: &str) -> bool {
+    let digits = number.chars().rev().filter_map(|c| c.to_digit(10));
+    let sum: u32 = digits
+        .enumerate()
+        .map(|(i, digit)| match (i % 2, digit * 2) {
+            (0, _) => digit,
+            (_, doubled) if doubled > 9 => doubled - 9,
+            (_, doubled) => doubled,
+        })
+        .sum();
+    sum % 10 == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn masks_known_keys() {
+        let redaction = RedactionConfig::default();
+
+        assert_eq!(
+            redaction.redact("login password=hunter2 ok"),
+            "login password=[REDACTED] ok"
+        );
+        assert_eq!(
+            redaction.redact(r#"{"api_key": "abc123", "user": "bob"}"#),
+            r#"{"api_key": "[REDACTED]", "user": "bob"}"#
+        );
+        assert_eq!(
+            redaction.redact("Authorization: Bearer abc.def"),
+            "Authorization: [REDACTED]"
+        );
+    }
+
+    #[test]
+    fn key_match_ignores_case_but_not_longer_keys() {
+        let redaction = RedactionConfig::none().with_keys(["token"]);
+
+        assert_eq!(redaction.redact("TOKEN=abc"), "TOKEN=[REDACTED]");
+        assert_eq!(redaction.redact("tokens=3"), "tokens=3");
+    }
+
+    #[test]
+    fn masks_prefixed_keys() {
+        let redaction = RedactionConfig::default();
+
+        assert_eq!(
+            redaction.redact("access_token=abc refresh_token=def"),
+            "access_token=[REDACTED] refresh_token=[REDACTED]"
+        );
+        assert_eq!(
+            redaction.redact(r#"{"client_secret": "xyz", "auth_token": "123"}"#),
+            r#"{"client_secret": "[REDACTED]", "auth_token": "[REDACTED]"}"#
+        );
+        assert_eq!(
+            redaction.redact("X-Auth-Token: abc"),
+            "X-Auth-Token: [REDACTED]"
+        );
+    }
+
+    #[test]
+    fn masks_quoted_values_up_to_closing_quote() {
+        let redaction = RedactionConfig::default();
+
+        assert_eq!(
+            redaction.redact(r#"password="correct horse" user=bob"#),
+            r#"password="[REDACTED]" user=bob"#
+        );
+        assert_eq!(
+            redaction.redact("secret='battery staple'"),
+            "secret='[REDACTED]'"
+        );
+        assert_eq!(
+            redaction.redact(r#"token="unterminated value"#),
+            r#"token="[REDACTED]"#
+        );
+    }
+
+    #[test]
+    fn default_keeps_emails_and_numbers() {
+        let text = "user jane@example.com at 1728030600250123";
+
+        assert_eq!(RedactionConfig::default().redact(text), text);
+    }
+
+    #[test]
+    fn masks_custom_patterns() {
+        let redaction = RedactionConfig::none()
+            .with_pattern(r"sk_live_[0-9A-Za-z]+")
+            .unwrap();
+
+        assert_eq!(
+            redaction.redact("charged with sk_live_4eC39Hq"),
+            "charged with [REDACTED]"
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_patterns() {
+        assert!(RedactionConfig::none().with_pattern("(").is_err());
+    }
+
+    #[test]
+    fn masks_emails() {
+        let redaction = RedactionConfig::none().with_emails(true);
+
+        assert_eq!(
+            redaction.redact("sent to jane.doe+test@example.co.uk today"),
+            "sent to [REDACTED] today"
+        );
+    }
+
+    #[test]
+    fn masks_luhn_valid_card_numbers() {
+        let redaction = RedactionConfig::none().with_credit_cards(true);
+
+        assert_eq!(
+            redaction.redact("card 4111 1111 1111 1111 declined"),
+            "card [REDACTED] declined"
+        );
+        assert_eq!(
+            redaction.redact("card 5500-0000-0000-0004"),
+            "card [REDACTED]"
+        );
+    }
+
+    #[test]
+    fn keeps_numbers_failing_luhn() {
+        let redaction = RedactionConfig::none().with_credit_cards(true);
+
+        assert_eq!(
+            redaction.redact("order 1234567890123 shipped"),
+            "order 1234567890123 shipped"
+        );
+    }
+
+    #[test]
+    fn none_masks_nothing() {
+        let text = "password=hunter2 jane@example.com 4111111111111111";
+
+        assert_eq!(RedactionConfig::none().redact(text), text);
+    }
+
+    #[test]
+    fn empty_keys_mask_nothing() {
+        let redaction = RedactionConfig::none().with_keys(Vec::<String>::new());
+
+        assert_eq!(redaction.redact("user=bob id: 42"), "user=bob id: 42");
+    }
+
+    #[test]
+    fn custom_mask() {
+        let redaction = RedactionConfig::none().with_emails(true).with_mask("***");
+
+        assert_eq!(redaction.redact("jane@example.com"), "***");
+    }
+}
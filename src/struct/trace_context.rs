@@ -0,0 +1,137 @@
+use serde::Serialize;
+
+/// The trace a record belongs to, serialized as top-level `trace_id`, `span_id` and
+/// `trace_flags` in W3C hex form.
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct TraceContext {
+    pub trace_id: String,
+    pub span_id: String,
+    pub trace_flags: String,
+}
+
+impl TraceContext {
+    /// Parses a W3C `traceparent` header such as
+    /// `00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01`.
+    pub fn from_traceparent(traceparent: &str) -> Option<Self> {
+        let mut parts = traceparent.trim().split('-');
+        let version = parts.next()?;
+        let trace_id = parts.next()?;
+        let span_id = parts.next()?;
+        let trace_flags = parts.next()?;
+        // later versions may append fields, version 00 may not
+        if !is_hex(version, 2) || version == "ff" || (version == "00" && parts.next().is_some()) {
+            return None;
+        }
+        if !is_hex(trace_id, 32) || !is_hex(span_id, 16) || !is_hex(trace_flags, 2) {
+            return None;
+        }
+        if is_zero(trace_id) || is_zero(span_id) {
+            return None;
+        }
+        Some(Self {
+            trace_id: trace_id.to_string(),
+            span_id: span_id.to_string(),
+            trace_flags: trace_flags.to_string(),
+        })
+    }
+
+    /// The span active in the current OpenTelemetry context, if it is valid.
+    #[cfg(feature = "opentelemetry")]
+    pub fn current() -> Option<Self> {
+        use opentelemetry::trace::TraceContextExt;
+
+        let context = opentelemetry::Context::current();
+        let span = context.span();
+        let span_context = span.span_context();
+        if !span_context.is_valid() {
+            return None;
+        }
+        Some(Self {
+            trace_id: span_context.trace_id().to_string(),
+            span_id: span_context.span_id().to_string(),
+            trace_flags: format!("{:02x}", span_context.trace_flags().to_u8()),
+        })
+    }
+}
+
+/// Lowercase hex of exactly `len` digits, as W3C Trace Context requires.
+fn is_hex(value: &str, len: usize) -> bool {
+    value.len() == len
+        && value
+            .bytes()
+            .all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))
+}
+
+fn is_zero(value: &str) -> bool {
+    value.bytes().all(|b| b == b'0')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TRACEPARENT: &str = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+
+    #[test]
+    fn parses_traceparent() {
+        let trace = TraceContext::from_traceparent(TRACEPARENT).unwrap();
+
+        assert_eq!(trace.trace_id, "4bf92f3577b34da6a3ce929d0e0e4736");
+        assert_eq!(trace.span_id, "00f067aa0ba902b7");
+        assert_eq!(trace.trace_flags, "01");
+    }
+
+    #[test]
+    fn accepts_future_versions_with_extra_fields() {
+        let traceparent = "cc-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01-extra";
+
+        assert!(TraceContext::from_traceparent(traceparent).is_some());
+    }
+
+    #[test]
+    fn rejects_malformed_traceparent() {
+        for traceparent in [
+            "",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01-extra",
+            "ff-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+            "00-4BF92F3577B34DA6A3CE929D0E0E4736-00f067aa0ba902b7-01",
+            "00-4bf92f3577b34da6a3ce929d0e0e473-00f067aa0ba902b7-01",
+            "00-00000000000000000000000000000000-00f067aa0ba902b7-01",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-0000000000000000-01",
+        ] {
+            assert_eq!(
+                TraceContext::from_traceparent(traceparent),
+                None,
+                "{}",
+                traceparent
+            );
+        }
+    }
+
+    #[cfg(feature = "opentelemetry")]
+    #[test]
+    fn reads_active_opentelemetry_span() {
+        use opentelemetry::trace::{
+            SpanContext, SpanId, TraceContextExt, TraceFlags, TraceId, TraceState,
+        };
+
+        assert_eq!(TraceContext::current(), None);
+
+        let span_context = SpanContext::new(
+            TraceId::from_hex("4bf92f3577b34da6a3ce929d0e0e4736").unwrap(),
+            SpanId::from_hex("00f067aa0ba902b7").unwrap(),
+            TraceFlags::SAMPLED,
+            true,
+            TraceState::default(),
+        );
+        let _guard = opentelemetry::Context::current()
+            .with_remote_span_context(span_context)
+            .attach();
+
+        assert_eq!(
+            TraceContext::current(),
+            TraceContext::from_traceparent(TRACEPARENT)
+        );
+    }
+}
@@ -0,0 +1,125 @@
+//! Recording doubles for asserting on what an application logs, enabled by the
+//! `test-util` feature.
+//!
+//! [`RecordingSink`] captures records as `Logger` fans them out, [`RecordingTransport`]
+//! captures the requests that would have been sent to BetterStack and answers them
+//! from a script, and [`FakeIngestServer`] does the same over real HTTP on localhost.
+//! [`SharedBuffer`] collects what a `ConsoleSink` prints.
+
+mod buffer;
+mod server;
+mod sink;
+mod transport;
+
+pub use buffer::SharedBuffer;
+pub use server::{FakeIngestServer, ReceivedRequest};
+pub use sink::RecordingSink;
+pub use transport::RecordingTransport;
+
+use crate::http_client::Encoding;
+use crate::r#struct::log_level::LogLevel;
+use serde_json::Value;
+use std::collections::VecDeque;
+use std::io;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// How a [`RecordingTransport`] or [`FakeIngestServer`] answers a request.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScriptedResponse {
+    /// `202 Accepted` with an empty body, as BetterStack answers.
+    Success,
+    /// A response with this status and an empty body.
+    Status(u16),
+    /// A failure to reach the server at all; the fake server drops the connection.
+    NetworkError(String),
+    /// Waits before answering, to exercise timeouts.
+    Delayed(Duration, Box<ScriptedResponse>),
+}
+
+impl ScriptedResponse {
+    pub fn delayed(self, delay: Duration) -> Self {
+        ScriptedResponse::Delayed(delay, Box::new(self))
+    }
+}
+
+/// Queue of scripted responses, falling back to a fixed one once exhausted.
+struct Script {
+    queue: Mutex<VecDeque<ScriptedResponse>>,
+    fallback: Mutex<ScriptedResponse>,
+}
+
+impl Default for Script {
+    fn default() -> Self {
+        Self {
+            queue: Mutex::new(VecDeque::new()),
+            fallback: Mutex::new(ScriptedResponse::Success),
+        }
+    }
+}
+
+impl Script {
+    fn push(&self, response: ScriptedResponse) {
+        self.queue.lock().unwrap().push_back(response);
+    }
+
+    fn set_fallback(&self, response: ScriptedResponse) {
+        *self.fallback.lock().unwrap() = response;
+    }
+
+    fn next(&self) -> ScriptedResponse {
+        let scripted = self.queue.lock().unwrap().pop_front();
+        scripted.unwrap_or_else(|| self.fallback.lock().unwrap().clone())
+    }
+}
+
+/// `body` with its `Content-Encoding` undone, gzip and zstd only with their features.
+fn decompress(content_encoding: Option<&str>, body: Vec<u8>) -> io::Result<Vec<u8>> {
+    match content_encoding {
+        #[cfg(feature = "compression")]
+        Some("gzip") => {
+            use std::io::Read;
+            let mut decoded = Vec::new();
+            flate2::read::GzDecoder::new(body.as_slice()).read_to_end(&mut decoded)?;
+            Ok(decoded)
+        }
+        #[cfg(feature = "zstd")]
+        Some("zstd") => zstd::decode_all(body.as_slice()),
+        _ => Ok(body),
+    }
+}
+
+/// Records in a JSON or MessagePack body, one per array element.
+fn decode_logs(content_type: &str, body: &[u8]) -> Vec<Value> {
+    let decoded = if content_type == Encoding::MessagePack.content_type() {
+        rmp_serde::from_slice(body).ok()
+    } else {
+        serde_json::from_slice(body).ok()
+    };
+    match decoded {
+        Some(Value::Array(logs)) => logs,
+        Some(log) => vec![log],
+        None => Vec::new(),
+    }
+}
+
+/// Whether a serialized record has `level` and a message containing `needle`.
+fn value_matches(log: &Value, level: &LogLevel, needle: &str) -> bool {
+    let level = serde_json::to_value(level).unwrap();
+    log["level"] == level
+        && log["message"]
+            .as_str()
+            .is_some_and(|message| message.contains(needle))
+}
+
+/// One `level: message` line per record, for assertion failures.
+fn describe<'a>(logs: impl Iterator<Item = &'a Value>) -> String {
+    let lines: Vec<String> = logs
+        .map(|log| format!("  {}: {}", log["level"], log["message"]))
+        .collect();
+    if lines.is_empty() {
+        "  (nothing logged)".to_string()
+    } else {
+        lines.join("\n")
+    }
+}
@@ -0,0 +1,431 @@
+use super::{decode_logs, decompress, describe, value_matches, Script, ScriptedResponse};
+use crate::http_client::Encoding;
+use crate::r#struct::log_level::LogLevel;
+use serde_json::Value;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+/// A request as received by [`FakeIngestServer`], body already decompressed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReceivedRequest {
+    pub method: String,
+    pub path: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+    /// Status the server answered with, `None` when it dropped the connection instead.
+    pub status: Option<u16>,
+}
+
+impl ReceivedRequest {
+    /// Value of the first header named `name`, ignoring case.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    fn logs(&self) -> Vec<Value> {
+        decode_logs(self.header("content-type").unwrap_or_default(), &self.body)
+    }
+}
+
+/// BetterStack-compatible ingestion endpoint listening on localhost.
+///
+/// Requests need `Authorization: Bearer <token>` and a JSON or MessagePack
+/// `Content-Type`, otherwise they are answered `401` or `415` like the real service.
+/// With the `compression` feature gzip (and, with `zstd`, zstd) bodies are decompressed. Scripted
+/// responses override the validation, so 429s, 500s and slow answers can be injected.
+///
+/// Point a logger at it through [`EnvConfig::logs_url`](crate::EnvConfig::logs_url).
+/// The server stops when dropped.
+pub struct FakeIngestServer {
+    address: SocketAddr,
+    state: Arc<ServerState>,
+}
+
+struct ServerState {
+    token: String,
+    requests: Mutex<Vec<ReceivedRequest>>,
+    script: Script,
+    shutdown: AtomicBool,
+}
+
+impl FakeIngestServer {
+    /// Starts listening on an ephemeral localhost port, accepting `token`.
+    pub fn start(token: impl Into<String>) -> io::Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let address = listener.local_addr()?;
+        let state = Arc::new(ServerState {
+            token: token.into(),
+            requests: Mutex::new(Vec::new()),
+            script: Script::default(),
+            shutdown: AtomicBool::new(false),
+        });
+
+        let accept_state = state.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                if accept_state.shutdown.load(Ordering::SeqCst) {
+                    break;
+                }
+                if let Ok(stream) = stream {
+                    let state = accept_state.clone();
+                    thread::spawn(move || serve_connection(stream, &state));
+                }
+            }
+        });
+
+        Ok(Self { address, state })
+    }
+
+    /// Base URL to use as `logs_url`, e.g. `http://127.0.0.1:49152`.
+    pub fn url(&self) -> String {
+        format!("http://{}", self.address)
+    }
+
+    /// Queues `response` for the next unanswered request.
+    pub fn then_respond(&self, response: ScriptedResponse) -> &Self {
+        self.state.script.push(response);
+        self
+    }
+
+    /// Answers with `response` once the queue is exhausted, instead of validating.
+    pub fn always_respond(&self, response: ScriptedResponse) -> &Self {
+        self.state.script.set_fallback(response);
+        self
+    }
+
+    pub fn requests(&self) -> Vec<ReceivedRequest> {
+        self.state.requests.lock().unwrap().clone()
+    }
+
+    /// Records from every accepted (`2xx`) request, batches flattened.
+    pub fn logs(&self) -> Vec<Value> {
+        self.requests()
+            .iter()
+            .filter(|request| matches!(request.status, Some(200..=299)))
+            .flat_map(ReceivedRequest::logs)
+            .collect()
+    }
+
+    /// Whether an accepted record has `level` and a message containing `needle`.
+    pub fn contains(&self, level: LogLevel, needle: &str) -> bool {
+        self.logs()
+            .iter()
+            .any(|log| value_matches(log, &level, needle))
+    }
+
+    #[track_caller]
+    pub fn assert_logged(&self, level: LogLevel, needle: &str) {
+        if !self.contains(level.clone(), needle) {
+            panic!(
+                "expected a {:?} record containing {:?} to be received, got:\n{}",
+                level,
+                needle,
+                describe(self.logs().iter())
+            );
+        }
+    }
+}
+
+impl Drop for FakeIngestServer {
+    fn drop(&mut self) {
+        self.state.shutdown.store(true, Ordering::SeqCst);
+        // wakes the accept loop up so it notices the shutdown
+        let _ = TcpStream::connect(self.address);
+    }
+}
+
+/// Answers requests on one keep-alive connection until the client closes it.
+fn serve_connection(stream: TcpStream, state: &ServerState) {
+    let Ok(read_half) = stream.try_clone() else {
+        return;
+    };
+    let mut reader = BufReader::new(read_half);
+    let mut writer = stream;
+
+    while let Ok(Some(mut request)) = read_request(&mut reader) {
+        let mut response = state.script.next();
+        while let ScriptedResponse::Delayed(delay, inner) = response {
+            thread::sleep(delay);
+            response = *inner;
+        }
+
+        let status = match response {
+            ScriptedResponse::Success => validate(&request, &state.token),
+            ScriptedResponse::Status(status) => status,
+            ScriptedResponse::NetworkError(_) => {
+                state.requests.lock().unwrap().push(request);
+                let _ = writer.shutdown(Shutdown::Both);
+                return;
+            }
+            ScriptedResponse::Delayed(..) => unreachable!(),
+        };
+
+        request.status = Some(status);
+        state.requests.lock().unwrap().push(request);
+        let head = format!(
+            "HTTP/1.1 {} {}\r\nContent-Length: 0\r\n\r\n",
+            status,
+            reason(status)
+        );
+        if writer.write_all(head.as_bytes()).is_err() {
+            return;
+        }
+    }
+}
+
+fn validate(request: &ReceivedRequest, token: &str) -> u16 {
+    let expected_auth = format!("Bearer {}", token);
+    let content_type = request.header("content-type").unwrap_or_default();
+    let supported = [Encoding::Json, Encoding::MessagePack]
+        .iter()
+        .any(|encoding| encoding.content_type() == content_type);
+
+    if request.header("authorization") != Some(expected_auth.as_str()) {
+        401
+    } else if !supported {
+        415
+    } else {
+        202
+    }
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        202 => "Accepted",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        415 => "Unsupported Media Type",
+        429 => "Too Many Requests",
+        500 => "Internal Server Error",
+        503 => "Service Unavailable",
+        _ => "Status",
+    }
+}
+
+/// Reads one request, `None` once the client closed the connection.
+fn read_request(reader: &mut impl BufRead) -> io::Result<Option<ReceivedRequest>> {
+    let mut request_line = String::new();
+    if reader.read_line(&mut request_line)? == 0 {
+        return Ok(None);
+    }
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default().to_string();
+    let path = parts.next().unwrap_or_default().to_string();
+
+    let mut headers = Vec::new();
+    loop {
+        let mut line = String::new();
+        reader.read_line(&mut line)?;
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            headers.push((name.trim().to_string(), value.trim().to_string()));
+        }
+    }
+
+    let mut request = ReceivedRequest {
+        method,
+        path,
+        headers,
+        body: Vec::new(),
+        status: None,
+    };
+    let length = request
+        .header("content-length")
+        .and_then(|length| length.parse().ok())
+        .unwrap_or(0);
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body)?;
+    request.body = decompress(request.header("content-encoding"), body)?;
+
+    Ok(Some(request))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http_client::{service, HttpClient, HttpRequest, LogtailError, ReqwestClient};
+    use crate::r#struct::betterstack_log_schema::BetterStackLogSchema;
+    use crate::r#struct::env_config::{EnvConfig, EnvEnum};
+    use crate::{LogSchema, Logger, OutputConfig};
+    use std::time::{Duration, Instant};
+
+    fn config_for(server: &FakeIngestServer, token: &str) -> EnvConfig {
+        let mut config =
+            EnvConfig::from_values("1.0.0".to_string(), EnvEnum::QA, token.to_string(), false);
+        config.logs_url = server.url();
+        config
+    }
+
+    fn test_log(message: &str) -> BetterStackLogSchema {
+        BetterStackLogSchema::fixture(LogLevel::Error, message)
+    }
+
+    #[tokio::test]
+    async fn logger_ships_end_to_end() {
+        let server = FakeIngestServer::start("secret").unwrap();
+        let logger = Logger::with_client(config_for(&server, "secret"), ReqwestClient::default())
+            .with_output(OutputConfig {
+                remote: true,
+                console: false,
+            });
+
+        logger
+            .info(LogSchema {
+                message: "server started".to_string(),
+                context: "main.rs".to_string(),
+            })
+            .await;
+        logger
+            .error(LogSchema {
+                message: "db unreachable".to_string(),
+                context: "db.rs".to_string(),
+            })
+            .await;
+
+        server.assert_logged(LogLevel::Info, "server started");
+        server.assert_logged(LogLevel::Error, "unreachable");
+        let requests = server.requests();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[0].method, "POST");
+        assert_eq!(requests[0].status, Some(202));
+    }
+
+    #[tokio::test]
+    async fn rejects_wrong_token() {
+        let server = FakeIngestServer::start("secret").unwrap();
+
+        let result = service::send_log(
+            &ReqwestClient::default(),
+            &config_for(&server, "wrong"),
+            &test_log("hello"),
+        )
+        .await;
+
+        assert!(matches!(
+            result,
+            Err(LogtailError::Http { status: 401, .. })
+        ));
+        assert!(server.logs().is_empty());
+        assert_eq!(server.requests()[0].status, Some(401));
+    }
+
+    #[tokio::test]
+    async fn rejects_unsupported_content_type() {
+        let server = FakeIngestServer::start("secret").unwrap();
+        let request = HttpRequest {
+            url: server.url(),
+            body: b"hello".to_vec(),
+            content_type: "text/plain".to_string(),
+            content_encoding: None,
+            headers: vec![("Authorization".to_string(), "Bearer secret".to_string())],
+        };
+
+        let response = ReqwestClient::default().send(request).await.unwrap();
+
+        assert_eq!(response.status, 415);
+    }
+
+    #[tokio::test]
+    async fn accepts_msgpack_batches() {
+        let server = FakeIngestServer::start("secret").unwrap();
+        let client = ReqwestClient::default().with_encoding(Encoding::MessagePack);
+
+        service::send_logs(
+            &client,
+            &config_for(&server, "secret"),
+            &[test_log("one"), test_log("two")],
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(server.logs().len(), 2);
+        server.assert_logged(LogLevel::Error, "two");
+    }
+
+    #[tokio::test]
+    async fn injects_scripted_statuses() {
+        let server = FakeIngestServer::start("secret").unwrap();
+        server
+            .then_respond(ScriptedResponse::Status(429))
+            .then_respond(ScriptedResponse::Status(500));
+        let config = config_for(&server, "secret");
+        let client = ReqwestClient::default();
+
+        let first = service::send_log(&client, &config, &test_log("a")).await;
+        let second = service::send_log(&client, &config, &test_log("b")).await;
+        let third = service::send_log(&client, &config, &test_log("c")).await;
+
+        assert!(matches!(first, Err(LogtailError::Http { status: 429, .. })));
+        assert!(matches!(
+            second,
+            Err(LogtailError::Http { status: 500, .. })
+        ));
+        assert!(third.is_ok());
+        assert_eq!(server.logs().len(), 1);
+        server.assert_logged(LogLevel::Error, "c");
+    }
+
+    #[tokio::test]
+    async fn delays_slow_responses() {
+        let server = FakeIngestServer::start("secret").unwrap();
+        server.then_respond(ScriptedResponse::Success.delayed(Duration::from_millis(100)));
+
+        let started = Instant::now();
+        service::send_log(
+            &ReqwestClient::default(),
+            &config_for(&server, "secret"),
+            &test_log("slow"),
+        )
+        .await
+        .unwrap();
+
+        assert!(started.elapsed() >= Duration::from_millis(100));
+    }
+
+    #[tokio::test]
+    async fn network_error_drops_connection() {
+        let server = FakeIngestServer::start("secret").unwrap();
+        server.then_respond(ScriptedResponse::NetworkError("reset".to_string()));
+
+        let result = service::send_log(
+            &ReqwestClient::default(),
+            &config_for(&server, "secret"),
+            &test_log("lost"),
+        )
+        .await;
+
+        assert!(matches!(result, Err(LogtailError::Network(_))));
+        assert_eq!(server.requests()[0].status, None);
+    }
+
+    #[cfg(feature = "compression")]
+    #[tokio::test]
+    async fn decompresses_gzip_bodies() {
+        use crate::http_client::{Compression, CompressionConfig};
+
+        let server = FakeIngestServer::start("secret").unwrap();
+        let client = ReqwestClient::default().with_compression(CompressionConfig {
+            algorithm: Compression::Gzip,
+            min_bytes: 0,
+        });
+
+        service::send_log(&client, &config_for(&server, "secret"), &test_log("zipped"))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            server.requests()[0].header("content-encoding"),
+            Some("gzip")
+        );
+        server.assert_logged(LogLevel::Error, "zipped");
+    }
+}
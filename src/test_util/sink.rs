@@ -0,0 +1,137 @@
+use super::{describe, value_matches};
+use crate::r#struct::betterstack_log_schema::BetterStackLogSchema;
+use crate::r#struct::log_level::LogLevel;
+use crate::sink::{Sink, SinkFuture};
+use serde_json::Value;
+use std::future;
+use std::sync::{Arc, Mutex};
+
+/// Sink keeping every record it receives in memory.
+///
+/// Clones share the same records, so keep one and register the other with
+/// [`Logger::with_sink`](crate::Logger::with_sink).
+#[derive(Clone, Default)]
+pub struct RecordingSink {
+    records: Arc<Mutex<Vec<BetterStackLogSchema>>>,
+}
+
+impl RecordingSink {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn records(&self) -> Vec<BetterStackLogSchema> {
+        self.records.lock().unwrap().clone()
+    }
+
+    pub fn clear(&self) {
+        self.records.lock().unwrap().clear();
+    }
+
+    /// Whether a record has `level` and a message containing `needle`.
+    pub fn contains(&self, level: LogLevel, needle: &str) -> bool {
+        self.values()
+            .iter()
+            .any(|log| value_matches(log, &level, needle))
+    }
+
+    #[track_caller]
+    pub fn assert_logged(&self, level: LogLevel, needle: &str) {
+        if !self.contains(level.clone(), needle) {
+            panic!(
+                "expected a {:?} record containing {:?}, got:\n{}",
+                level,
+                needle,
+                describe(self.values().iter())
+            );
+        }
+    }
+
+    #[track_caller]
+    pub fn assert_not_logged(&self, level: LogLevel, needle: &str) {
+        if self.contains(level.clone(), needle) {
+            panic!(
+                "expected no {:?} record containing {:?}, got:\n{}",
+                level,
+                needle,
+                describe(self.values().iter())
+            );
+        }
+    }
+
+    fn values(&self) -> Vec<Value> {
+        let records = self.records.lock().unwrap();
+        records
+            .iter()
+            .map(|record| serde_json::to_value(record).unwrap())
+            .collect()
+    }
+}
+
+impl Sink for RecordingSink {
+    fn write<'a>(&'a self, log: &'a BetterStackLogSchema) -> SinkFuture<'a> {
+        self.records.lock().unwrap().push(log.clone());
+        Box::pin(future::ready(Ok(())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_log(level: LogLevel, message: &str) -> BetterStackLogSchema {
+        BetterStackLogSchema::fixture(level, message)
+    }
+
+    #[tokio::test]
+    async fn records_what_it_receives() {
+        let sink = RecordingSink::new();
+        let handle = sink.clone();
+
+        sink.write(&test_log(LogLevel::Info, "hello"))
+            .await
+            .unwrap();
+
+        assert_eq!(handle.records().len(), 1);
+        assert_eq!(handle.records()[0].message, "hello");
+    }
+
+    #[tokio::test]
+    async fn contains_matches_level_and_substring() {
+        let sink = RecordingSink::new();
+        sink.write(&test_log(LogLevel::Error, "db connection refused"))
+            .await
+            .unwrap();
+
+        assert!(sink.contains(LogLevel::Error, "connection refused"));
+        assert!(!sink.contains(LogLevel::Warn, "connection refused"));
+        assert!(!sink.contains(LogLevel::Error, "timeout"));
+        sink.assert_logged(LogLevel::Error, "refused");
+        sink.assert_not_logged(LogLevel::Info, "refused");
+    }
+
+    #[tokio::test]
+    #[should_panic(
+        expected = "expected a Error record containing \"timeout\", got:\n  \"Info\": \"hello\""
+    )]
+    async fn assert_logged_lists_records_on_failure() {
+        let sink = RecordingSink::new();
+        sink.write(&test_log(LogLevel::Info, "hello"))
+            .await
+            .unwrap();
+
+        sink.assert_logged(LogLevel::Error, "timeout");
+    }
+
+    #[tokio::test]
+    async fn clear_forgets_records() {
+        let sink = RecordingSink::new();
+        sink.write(&test_log(LogLevel::Info, "hello"))
+            .await
+            .unwrap();
+
+        sink.clear();
+
+        assert!(sink.records().is_empty());
+    }
+}
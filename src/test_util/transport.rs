@@ -0,0 +1,256 @@
+use super::{decode_logs, decompress, describe, value_matches, Script, ScriptedResponse};
+use crate::http_client::{Encoding, HttpClient, HttpRequest, HttpResponse, LogtailError};
+use crate::r#struct::log_level::LogLevel;
+use serde_json::Value;
+use std::sync::{Arc, Mutex};
+
+/// [`HttpClient`] recording every request instead of sending it.
+///
+/// Requests are answered from a queue of [`ScriptedResponse`]s, then with the fallback
+/// response once the queue is empty. Clones share the same state, so keep one and hand
+/// the other to [`Logger::with_client`](crate::Logger::with_client).
+#[derive(Clone)]
+pub struct RecordingTransport {
+    state: Arc<TransportState>,
+}
+
+struct TransportState {
+    encoding: Encoding,
+    requests: Mutex<Vec<HttpRequest>>,
+    script: Script,
+}
+
+impl Default for RecordingTransport {
+    fn default() -> Self {
+        Self::with_encoding(Encoding::Json)
+    }
+}
+
+impl RecordingTransport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_encoding(encoding: Encoding) -> Self {
+        Self {
+            state: Arc::new(TransportState {
+                encoding,
+                requests: Mutex::new(Vec::new()),
+                script: Script::default(),
+            }),
+        }
+    }
+
+    /// Queues `response` for the next unanswered request.
+    pub fn then_respond(&self, response: ScriptedResponse) -> &Self {
+        self.state.script.push(response);
+        self
+    }
+
+    /// Answers with `response` once the queue is exhausted.
+    pub fn always_respond(&self, response: ScriptedResponse) -> &Self {
+        self.state.script.set_fallback(response);
+        self
+    }
+
+    pub fn requests(&self) -> Vec<HttpRequest> {
+        self.state.requests.lock().unwrap().clone()
+    }
+
+    pub fn request_count(&self) -> usize {
+        self.state.requests.lock().unwrap().len()
+    }
+
+    /// Every record sent so far, decoded from the request bodies with batches flattened.
+    ///
+    /// Bodies the caller already compressed are decompressed first, like
+    /// [`FakeIngestServer`](super::FakeIngestServer) does.
+    pub fn logs(&self) -> Vec<Value> {
+        self.requests()
+            .into_iter()
+            .flat_map(|request| {
+                let body = decompress(request.content_encoding.as_deref(), request.body)
+                    .unwrap_or_default();
+                decode_logs(&request.content_type, &body)
+            })
+            .collect()
+    }
+
+    /// Whether a sent record has `level` and a message containing `needle`.
+    pub fn contains(&self, level: LogLevel, needle: &str) -> bool {
+        self.logs()
+            .iter()
+            .any(|log| value_matches(log, &level, needle))
+    }
+
+    #[track_caller]
+    pub fn assert_logged(&self, level: LogLevel, needle: &str) {
+        if !self.contains(level.clone(), needle) {
+            panic!(
+                "expected a {:?} record containing {:?} to be sent, got:\n{}",
+                level,
+                needle,
+                describe(self.logs().iter())
+            );
+        }
+    }
+}
+
+impl HttpClient for RecordingTransport {
+    async fn send(&self, request: HttpRequest) -> Result<HttpResponse, LogtailError> {
+        self.state.requests.lock().unwrap().push(request);
+
+        let mut response = self.state.script.next();
+        while let ScriptedResponse::Delayed(delay, inner) = response {
+            tokio::time::sleep(delay).await;
+            response = *inner;
+        }
+
+        match response {
+            ScriptedResponse::Success => Ok(HttpResponse {
+                status: 202,
+                ..HttpResponse::default()
+            }),
+            ScriptedResponse::Status(status) => Ok(HttpResponse {
+                status,
+                ..HttpResponse::default()
+            }),
+            ScriptedResponse::NetworkError(message) => Err(LogtailError::Network(message.into())),
+            ScriptedResponse::Delayed(..) => unreachable!(),
+        }
+    }
+
+    fn encoding(&self) -> Encoding {
+        self.state.encoding
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http_client::service;
+    use crate::r#struct::betterstack_log_schema::BetterStackLogSchema;
+    use crate::r#struct::env_config::{EnvConfig, EnvEnum};
+    use std::time::{Duration, Instant};
+
+    fn test_config() -> EnvConfig {
+        EnvConfig::from_values("1.0.0".to_string(), EnvEnum::QA, "token".to_string(), false)
+    }
+
+    fn test_log(message: &str) -> BetterStackLogSchema {
+        BetterStackLogSchema::fixture(LogLevel::Error, message)
+    }
+
+    #[tokio::test]
+    async fn records_requests_and_decodes_logs() {
+        let transport = RecordingTransport::new();
+
+        service::send_log(&transport, &test_config(), &test_log("one"))
+            .await
+            .unwrap();
+        service::send_logs(
+            &transport,
+            &test_config(),
+            &[test_log("two"), test_log("three")],
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(transport.request_count(), 2);
+        let messages: Vec<Value> = transport
+            .logs()
+            .iter()
+            .map(|log| log["message"].clone())
+            .collect();
+        assert_eq!(messages, vec!["one", "two", "three"]);
+        transport.assert_logged(LogLevel::Error, "thr");
+        assert!(!transport.contains(LogLevel::Info, "one"));
+    }
+
+    #[tokio::test]
+    async fn decodes_msgpack_bodies() {
+        let transport = RecordingTransport::with_encoding(Encoding::MessagePack);
+
+        service::send_log(&transport, &test_config(), &test_log("packed"))
+            .await
+            .unwrap();
+
+        assert_eq!(transport.requests()[0].content_type, "application/msgpack");
+        transport.assert_logged(LogLevel::Error, "packed");
+    }
+
+    #[cfg(feature = "compression")]
+    #[tokio::test]
+    async fn decompresses_precompressed_bodies() {
+        use flate2::write::GzEncoder;
+        use std::io::Write;
+
+        let transport = RecordingTransport::new();
+        let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder
+            .write_all(&serde_json::to_vec(&[test_log("zipped")]).unwrap())
+            .unwrap();
+        let request = HttpRequest {
+            url: "http://localhost".to_string(),
+            body: encoder.finish().unwrap(),
+            content_type: "application/json".to_string(),
+            content_encoding: Some("gzip".to_string()),
+            headers: Vec::new(),
+        };
+
+        transport.send(request).await.unwrap();
+
+        transport.assert_logged(LogLevel::Error, "zipped");
+    }
+
+    #[tokio::test]
+    async fn answers_from_script_then_fallback() {
+        let transport = RecordingTransport::new();
+        transport
+            .then_respond(ScriptedResponse::Status(500))
+            .then_respond(ScriptedResponse::NetworkError("refused".to_string()));
+        transport.always_respond(ScriptedResponse::Status(429));
+
+        let first = service::send_log(&transport, &test_config(), &test_log("a")).await;
+        let second = service::send_log(&transport, &test_config(), &test_log("b")).await;
+        let third = service::send_log(&transport, &test_config(), &test_log("c")).await;
+        let fourth = service::send_log(&transport, &test_config(), &test_log("d")).await;
+
+        assert!(matches!(first, Err(LogtailError::Http { status: 500, .. })));
+        assert!(matches!(second, Err(LogtailError::Network(_))));
+        assert!(matches!(third, Err(LogtailError::Http { status: 429, .. })));
+        assert!(matches!(
+            fourth,
+            Err(LogtailError::Http { status: 429, .. })
+        ));
+        assert_eq!(transport.request_count(), 4);
+    }
+
+    #[tokio::test]
+    async fn delayed_response_waits() {
+        let transport = RecordingTransport::new();
+        transport.then_respond(ScriptedResponse::Success.delayed(Duration::from_millis(50)));
+
+        let started = Instant::now();
+        service::send_log(&transport, &test_config(), &test_log("slow"))
+            .await
+            .unwrap();
+
+        assert!(started.elapsed() >= Duration::from_millis(50));
+    }
+
+    #[tokio::test]
+    async fn logger_ships_through_recording_transport() {
+        let transport = RecordingTransport::new();
+        let logger = crate::Logger::with_client(test_config(), transport.clone());
+
+        logger
+            .warn(crate::LogSchema {
+                message: "disk almost full".to_string(),
+                context: "ctx".to_string(),
+            })
+            .await;
+
+        transport.assert_logged(LogLevel::Warn, "almost full");
+    }
+}
@@ -0,0 +1,80 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const SECONDS_PER_DAY: u64 = 86_400;
+
+fn since_epoch(time: SystemTime) -> Duration {
+    time.duration_since(UNIX_EPOCH).unwrap_or_default()
+}
+
+/// Whole days elapsed since the Unix epoch, in UTC.
+pub(crate) fn days_since_epoch(time: SystemTime) -> u64 {
+    since_epoch(time).as_secs() / SECONDS_PER_DAY
+}
+
+/// Converts days since the Unix epoch to a `(year, month, day)` civil date.
+///
+/// Howard Hinnant's `civil_from_days` algorithm, restricted to dates after 1970.
+pub(crate) fn civil_from_days(days: u64) -> (u64, u64, u64) {
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + u64::from(month <= 2);
+    (year, month, day)
+}
+
+/// Formats days since the Unix epoch as `YYYY-MM-DD`.
+pub(crate) fn format_date(days: u64) -> String {
+    let (year, month, day) = civil_from_days(days);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// Formats a UTC RFC 3339 timestamp with millisecond precision, e.g. `2024-10-04T08:30:00.250Z`.
+pub(crate) fn format_rfc3339(time: SystemTime) -> String {
+    let elapsed = since_epoch(time);
+    let seconds = elapsed.as_secs() % SECONDS_PER_DAY;
+    format!(
+        "{}T{:02}:{:02}:{:02}.{:03}Z",
+        format_date(days_since_epoch(time)),
+        seconds / 3_600,
+        seconds % 3_600 / 60,
+        seconds % 60,
+        elapsed.subsec_millis()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn epoch_is_day_zero() {
+        assert_eq!(days_since_epoch(UNIX_EPOCH), 0);
+        assert_eq!(format_date(0), "1970-01-01");
+    }
+
+    #[test]
+    fn known_dates() {
+        assert_eq!(format_date(11_016), "2000-02-29");
+        assert_eq!(format_date(19_782), "2024-02-29");
+        assert_eq!(format_date(20_088), "2024-12-31");
+    }
+
+    #[test]
+    fn days_since_epoch_truncates_partial_days() {
+        let time = UNIX_EPOCH + Duration::from_secs(2 * SECONDS_PER_DAY - 1);
+        assert_eq!(days_since_epoch(time), 1);
+    }
+
+    #[test]
+    fn rfc3339_includes_time_and_millis() {
+        let time =
+            UNIX_EPOCH + Duration::from_millis(20_000 * SECONDS_PER_DAY * 1_000 + 30_600_250);
+        assert_eq!(format_rfc3339(time), "2024-10-04T08:30:00.250Z");
+        assert_eq!(format_rfc3339(UNIX_EPOCH), "1970-01-01T00:00:00.000Z");
+    }
+}